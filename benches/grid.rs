@@ -0,0 +1,96 @@
+//! How long keeping the ants in a `Grid` takes, against the 16.6ms of a frame at 60 FPS.
+//!
+//! Run with `cargo bench`.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use macroquad::math::{vec2, Rect, Vec2};
+
+use clicker1::grid::Grid;
+
+const SCENE: Rect = Rect {
+    x: 0.0,
+    y: 0.0,
+    w: 960.0,
+    h: 540.0,
+};
+const CELL: f32 = 24.0;
+
+/// Ants spread over the scene the same way every run.
+fn ants(amount: usize) -> Vec<Vec2> {
+    let mut seed = 0x2027u32;
+    let mut next = move || {
+        seed ^= seed << 13;
+        seed ^= seed >> 17;
+        seed ^= seed << 5;
+        seed as f32 / u32::MAX as f32
+    };
+    (0..amount)
+        .map(|_| vec2(next() * SCENE.w, next() * SCENE.h))
+        .collect()
+}
+
+fn rebuild(c: &mut Criterion) {
+    let mut group = c.benchmark_group("rebuild");
+    for amount in [1_000, 5_000, 20_000] {
+        let positions = ants(amount);
+        let mut grid = Grid::new(SCENE, CELL);
+        group.bench_with_input(
+            BenchmarkId::from_parameter(amount),
+            &positions,
+            |b, positions| {
+                b.iter(|| {
+                    grid.clear();
+                    for (idx, pos) in positions.iter().enumerate() {
+                        grid.insert(*pos, idx);
+                    }
+                    grid.build();
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+fn queries(c: &mut Criterion) {
+    let mut grid = Grid::new(SCENE, CELL);
+    for (idx, pos) in ants(5_000).into_iter().enumerate() {
+        grid.insert(pos, idx);
+    }
+    grid.build();
+    let pointer = vec2(480.0, 270.0);
+
+    c.bench_function("hit test 5000", |b| {
+        b.iter(|| grid.nearest(black_box(pointer), 8.0))
+    });
+    c.bench_function("area of effect 5000", |b| {
+        b.iter(|| grid.within(black_box(pointer), 24.0).count())
+    });
+    c.bench_function("cull 5000", |b| {
+        b.iter(|| grid.inside(black_box(SCENE)).count())
+    });
+}
+
+/// Everything the game does with the grid in a tick: rebuild it, a hit test and an area of effect
+/// for each of a few pointers, and culling for the draw.
+fn tick(c: &mut Criterion) {
+    let positions = ants(5_000);
+    let mut grid = Grid::new(SCENE, CELL);
+    let pointers = [vec2(200.0, 400.0), vec2(480.0, 270.0), vec2(700.0, 100.0)];
+    c.bench_function("tick 5000", |b| {
+        b.iter(|| {
+            grid.clear();
+            for (idx, pos) in positions.iter().enumerate() {
+                grid.insert(*pos, idx);
+            }
+            grid.build();
+            for pointer in pointers {
+                black_box(grid.nearest(pointer, 8.0));
+                black_box(grid.within(pointer, 24.0).count());
+            }
+            grid.inside(SCENE).count()
+        })
+    });
+}
+
+criterion_group!(benches, rebuild, queries, tick);
+criterion_main!(benches);
@@ -0,0 +1,271 @@
+use std::{collections::HashMap, fmt};
+
+use macroquad::{
+    audio::{load_sound, Sound},
+    color::{BLACK, MAGENTA},
+    file::load_string,
+    math::Rect,
+    texture::{load_image, load_texture, FilterMode, Image, Texture2D},
+};
+
+use crate::atlas::Atlas;
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum ResourceName {
+    ImageA1,
+    ImageB1,
+    /// every frame of every ant, see `sprite`
+    AntSheet,
+    Drone,
+    IconPause,
+    Story1,
+    Story2,
+    Story3,
+    MusicA1,
+    MusicB1,
+    Squish,
+    Deposit,
+    Cash,
+    Warn,
+    Win,
+    Lose,
+    LevelA1,
+    LevelB1,
+    StoryIntro,
+    StoryA1Won,
+}
+
+impl ResourceName {
+    /// Looks a name up the way it is spelled in code, for the data files.
+    pub fn parse(name: &str) -> Option<Self> {
+        MANIFEST
+            .iter()
+            .map(|(resource, _, _)| *resource)
+            .find(|resource| format!("{resource:?}") == name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AssetKind {
+    Texture,
+    /// small images that end up on the atlas rather than in a texture of their own
+    Sprite,
+    Sound,
+    /// level and story files, see `data`
+    Text,
+}
+
+/// Every file the game loads, and the name it is looked up by.
+#[rustfmt::skip]
+pub const MANIFEST: &[(ResourceName, AssetKind, &str)] = {
+    use AssetKind::*;
+    use ResourceName::*;
+    &[
+        (ImageA1, Texture, "./gimp/bg_a_1.png"),
+        (ImageB1, Texture, "./gimp/bg_b_1.png"),
+        (AntSheet, Sprite, "./gimp/ant_sheet.png"),
+        (Drone, Sprite, "./gimp/drone.png"),
+        (IconPause, Sprite, "./gimp/icon_pause.png"),
+        (Story1, Texture, "./gimp/story3.png"),
+        (Story2, Texture, "./gimp/story1.png"),
+        (Story3, Texture, "./gimp/story3.png"),
+        (MusicA1, Sound, "./sfx/music_a1.wav"),
+        (MusicB1, Sound, "./sfx/music_b1.wav"),
+        (Squish, Sound, "./sfx/squish.wav"),
+        (Deposit, Sound, "./sfx/deposit.wav"),
+        (Cash, Sound, "./sfx/cash.wav"),
+        (Warn, Sound, "./sfx/warn.wav"),
+        (Win, Sound, "./sfx/win.wav"),
+        (Lose, Sound, "./sfx/lose.wav"),
+        (LevelA1, Text, "./levels/a1.txt"),
+        (LevelB1, Text, "./levels/b1.txt"),
+        (StoryIntro, Text, "./story/intro.txt"),
+        (StoryA1Won, Text, "./story/a1_won.txt"),
+    ]
+};
+
+/// The manifest entry of `name`.
+fn entry(name: ResourceName) -> &'static (ResourceName, AssetKind, &'static str) {
+    MANIFEST
+        .iter()
+        .find(|(resource, _, _)| *resource == name)
+        .expect("every resource is in the manifest")
+}
+
+/// Where the file of `name` is, for reading it without the loader.
+pub fn path(name: ResourceName) -> &'static str {
+    entry(name).2
+}
+
+/// What sort of file `name` is.
+pub fn kind(name: ResourceName) -> AssetKind {
+    entry(name).1
+}
+
+#[derive(Default)]
+pub struct Resources {
+    pub textures: HashMap<ResourceName, Texture2D>,
+    pub sounds: HashMap<ResourceName, Sound>,
+    pub texts: HashMap<ResourceName, String>,
+    /// the sprites, before they are packed into `atlas`
+    pub images: HashMap<ResourceName, Image>,
+    pub atlas: Atlas,
+}
+
+impl Resources {
+    pub fn get(&self, name: &ResourceName) -> Option<&Texture2D> {
+        self.textures.get(name)
+    }
+
+    pub fn sound(&self, name: &ResourceName) -> Option<&Sound> {
+        self.sounds.get(name)
+    }
+
+    pub fn text(&self, name: &ResourceName) -> Option<&str> {
+        self.texts.get(name).map(String::as_str)
+    }
+
+    /// Where sprite `name` is on `atlas`.
+    pub fn sprite(&self, name: &ResourceName) -> Option<Rect> {
+        self.atlas.rect(name)
+    }
+
+    /// Puts the sprites on the atlas, after any of them was loaded.
+    pub fn pack_sprites(&mut self) {
+        self.atlas = Atlas::pack(&self.images);
+    }
+}
+
+/// An entry of the manifest that could not be loaded, or whose contents did not make sense.
+pub struct AssetError {
+    pub name: ResourceName,
+    pub path: &'static str,
+    pub error: String,
+}
+
+impl fmt::Display for AssetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} ({}): {}", self.name, self.path, self.error)
+    }
+}
+
+/// Loads the manifest one entry at a time so something can be drawn in between, carrying on past
+/// failures so they can all be reported at once. Textures and sprites that failed are replaced by
+/// a checkerboard, sounds that failed stay silent.
+pub struct Loader {
+    loaded: usize,
+    pub errors: Vec<AssetError>,
+}
+
+impl Default for Loader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Loader {
+    pub fn new() -> Self {
+        Loader {
+            loaded: 0,
+            errors: vec![],
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.loaded == MANIFEST.len()
+    }
+
+    /// 0.0 to 1.0
+    pub fn progress(&self) -> f32 {
+        self.loaded as f32 / MANIFEST.len() as f32
+    }
+
+    /// The file the next call to `load_next` will load.
+    pub fn next_path(&self) -> Option<&'static str> {
+        MANIFEST.get(self.loaded).map(|(_, _, path)| *path)
+    }
+
+    pub async fn load_next(&mut self, resources: &mut Resources) {
+        let Some(&(name, kind, path)) = MANIFEST.get(self.loaded) else {
+            return;
+        };
+        self.loaded += 1;
+        if let Err(error) = load_entry(resources, name, kind, path).await {
+            match kind {
+                AssetKind::Texture => {
+                    let texture = Texture2D::from_image(&placeholder());
+                    texture.set_filter(FilterMode::Nearest);
+                    resources.textures.insert(name, texture);
+                }
+                AssetKind::Sprite => {
+                    resources.images.insert(name, placeholder());
+                }
+                AssetKind::Sound | AssetKind::Text => {}
+            }
+            let error = AssetError { name, path, error };
+            eprintln!("failed to load {error}");
+            self.errors.push(error);
+        }
+        if self.is_done() {
+            resources.pack_sprites();
+        }
+    }
+}
+
+/// Loads a single entry of the manifest into `resources`, replacing whatever was there.
+pub async fn load_entry(
+    resources: &mut Resources,
+    name: ResourceName,
+    kind: AssetKind,
+    path: &str,
+) -> Result<(), String> {
+    match kind {
+        AssetKind::Texture => load_texture(path).await.map(|texture| {
+            // the art is pixel art, keep it sharp when the window scales it up
+            texture.set_filter(FilterMode::Nearest);
+            resources.textures.insert(name, texture);
+        }),
+        AssetKind::Sprite => load_image(path).await.map(|image| {
+            resources.images.insert(name, image);
+        }),
+        AssetKind::Sound => load_sound(path).await.map(|sound| {
+            resources.sounds.insert(name, sound);
+        }),
+        AssetKind::Text => load_string(path).await.map(|text| {
+            resources.texts.insert(name, text);
+        }),
+    }
+    .map_err(|err| err.to_string())
+}
+
+/// Stands in for an image that could not be loaded, loud enough to be noticed.
+fn placeholder() -> Image {
+    let mut image = Image::gen_image_color(16, 16, MAGENTA);
+    for y in 0..16u32 {
+        for x in 0..16 {
+            if (x / 4 + y / 4).is_multiple_of(2) {
+                image.set_pixel(x, y, BLACK);
+            }
+        }
+    }
+    image
+}
+
+/// Files in the asset directories that nothing in the manifest refers to.
+#[cfg(all(debug_assertions, not(target_arch = "wasm32")))]
+pub fn unused_files() -> Vec<String> {
+    let mut unused = vec![];
+    for dir in ["./gimp", "./sfx"] {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = format!("{dir}/{}", entry.file_name().to_string_lossy());
+            if !MANIFEST.iter().any(|(_, _, used)| *used == path) {
+                unused.push(path);
+            }
+        }
+    }
+    unused.sort();
+    unused
+}
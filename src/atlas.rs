@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+
+use macroquad::{
+    math::Rect,
+    texture::{FilterMode, Image, Texture2D},
+};
+
+use crate::assets::ResourceName;
+
+/// How wide the atlas is, unless a sprite is wider and it grows to fit.
+const WIDTH: u16 = 256;
+// keeps neighbouring sprites from bleeding into each other when scaled
+const PADDING: u16 = 1;
+
+/// The small images of the manifest packed into a single texture, so everything drawn from them
+/// can share a `SpriteBatch`.
+pub struct Atlas {
+    pub texture: Texture2D,
+    rects: HashMap<ResourceName, Rect>,
+}
+
+impl Default for Atlas {
+    fn default() -> Self {
+        Atlas {
+            texture: Texture2D::empty(),
+            rects: HashMap::new(),
+        }
+    }
+}
+
+impl Atlas {
+    /// Packs `images` in rows, tallest first.
+    pub fn pack(images: &HashMap<ResourceName, Image>) -> Self {
+        let sizes = images
+            .iter()
+            .map(|(name, image)| (*name, (image.width, image.height)))
+            .collect();
+        let (rects, (width, height)) = layout(&sizes);
+
+        let mut atlas = Image {
+            bytes: vec![0; width as usize * height as usize * 4],
+            width,
+            height,
+        };
+        for (name, rect) in &rects {
+            let image = &images[name];
+            let (x, y) = (rect.x as usize, rect.y as usize);
+            // a row of pixels at a time
+            let row = image.width as usize * 4;
+            for line in 0..image.height as usize {
+                let from = line * row;
+                let to = ((y + line) * width as usize + x) * 4;
+                atlas.bytes[to..to + row].copy_from_slice(&image.bytes[from..from + row]);
+            }
+        }
+
+        let texture = Texture2D::from_image(&atlas);
+        texture.set_filter(FilterMode::Nearest);
+        Atlas { texture, rects }
+    }
+
+    /// Where `name` is on the texture, in pixels.
+    pub fn rect(&self, name: &ResourceName) -> Option<Rect> {
+        self.rects.get(name).copied()
+    }
+}
+
+/// Where each of the images of `sizes` (width and height) goes on the atlas, and how big the atlas
+/// has to be for all of them.
+fn layout(sizes: &HashMap<ResourceName, (u16, u16)>) -> (HashMap<ResourceName, Rect>, (u16, u16)) {
+    let width = sizes
+        .values()
+        .map(|(width, _)| *width)
+        .fold(WIDTH, u16::max)
+        .next_power_of_two();
+    let mut order: Vec<_> = sizes.iter().collect();
+    // the name breaks ties so the layout is the same every time
+    order.sort_by_key(|(name, (_, height))| (u16::MAX - height, format!("{name:?}")));
+
+    let mut rects = HashMap::new();
+    let (mut x, mut y, mut row_height) = (0, 0, 0);
+    for (name, &(w, h)) in order {
+        if x + w > width {
+            (x, y, row_height) = (0, y + row_height + PADDING, 0);
+        }
+        rects.insert(*name, Rect::new(x as f32, y as f32, w as f32, h as f32));
+        x += w + PADDING;
+        row_height = row_height.max(h);
+    }
+    let height = (y + row_height).max(1).next_power_of_two();
+    (rects, (width, height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ResourceName::*;
+
+    fn check(sizes: &HashMap<ResourceName, (u16, u16)>) -> (u16, u16) {
+        let (rects, (width, height)) = layout(sizes);
+        assert_eq!(rects.len(), sizes.len());
+        for (name, rect) in &rects {
+            assert_eq!((rect.w as u16, rect.h as u16), sizes[name]);
+            assert!(rect.right() <= width as f32 && rect.bottom() <= height as f32);
+            // with the padding around it, nothing else is touched
+            let padded = Rect::new(
+                rect.x - PADDING as f32,
+                rect.y - PADDING as f32,
+                rect.w + 2.0 * PADDING as f32,
+                rect.h + 2.0 * PADDING as f32,
+            );
+            for (other, other_rect) in rects.iter().filter(|(other, _)| *other != name) {
+                assert!(
+                    padded
+                        .intersect(*other_rect)
+                        .is_none_or(|overlap| overlap.w * overlap.h == 0.0),
+                    "{name:?} at {rect:?} is too close to {other:?} at {other_rect:?}"
+                );
+            }
+        }
+        (width, height)
+    }
+
+    #[test]
+    fn sprites_are_spaced_apart_in_rows() {
+        let sizes = HashMap::from([
+            (AntSheet, (40, 32)),
+            (Drone, (16, 16)),
+            (IconPause, (16, 16)),
+            (ImageA1, (200, 10)),
+            (ImageB1, (100, 10)),
+            (Story1, (255, 1)),
+        ]);
+        assert_eq!(check(&sizes), (WIDTH, 64));
+        let (rects, _) = layout(&sizes);
+        assert_eq!(rects[&Drone].x, 40.0 + PADDING as f32);
+    }
+
+    #[test]
+    fn a_sprite_wider_than_the_atlas_makes_it_grow() {
+        let sizes = HashMap::from([(AntSheet, (40, 32)), (Story1, (300, 20))]);
+        assert_eq!(check(&sizes), (512, 32));
+    }
+}
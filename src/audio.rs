@@ -0,0 +1,69 @@
+use macroquad::audio::{play_sound, set_sound_volume, stop_sound, PlaySoundParams};
+
+use crate::assets::{ResourceName, Resources};
+
+/// Plays sound effects and keeps one music track looping.
+///
+/// Sounds are only heard when built with the `audio` feature. Without it macroquad still loads
+/// them but would print a warning on every play, so nothing is played at all.
+pub struct Jukebox {
+    music: Option<ResourceName>,
+    volume: f32,
+}
+
+impl Jukebox {
+    pub fn new(volume: f32) -> Self {
+        Jukebox {
+            music: None,
+            volume,
+        }
+    }
+
+    pub fn play(&self, resources: &Resources, name: &ResourceName) {
+        if let Some(sound) = resources.sound(name).filter(|_| cfg!(feature = "audio")) {
+            play_sound(
+                sound,
+                PlaySoundParams {
+                    looped: false,
+                    volume: self.volume,
+                },
+            );
+        }
+    }
+
+    /// Starts looping `music` unless it is already the one playing, `None` stops the music.
+    pub fn set_music(&mut self, resources: &Resources, music: Option<ResourceName>) {
+        if self.music == music {
+            return;
+        }
+        if let Some(sound) = self.music.as_ref().and_then(|name| resources.sound(name)) {
+            if cfg!(feature = "audio") {
+                stop_sound(sound);
+            }
+        }
+        if let Some(sound) = music.as_ref().and_then(|name| resources.sound(name)) {
+            if cfg!(feature = "audio") {
+                play_sound(
+                    sound,
+                    PlaySoundParams {
+                        looped: true,
+                        volume: self.volume,
+                    },
+                );
+            }
+        }
+        self.music = music;
+    }
+
+    pub fn set_volume(&mut self, resources: &Resources, volume: f32) {
+        if self.volume == volume {
+            return;
+        }
+        self.volume = volume;
+        if let Some(sound) = self.music.as_ref().and_then(|name| resources.sound(name)) {
+            if cfg!(feature = "audio") {
+                set_sound_volume(sound, volume);
+            }
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use macroquad::{
+    color::Color,
+    math::{vec2, Rect, Vec2},
+    models::{draw_mesh, Mesh, Vertex},
+    texture::Texture2D,
+};
+
+// macroquad clamps anything past 5000 indices in one draw call, at 6 indices a quad
+const MAX_QUADS: usize = 800;
+
+/// Collects sprites cut from a single texture and draws them with as few draw calls as possible,
+/// instead of one call per sprite.
+///
+/// Sprites are drawn in the order they are pushed, whenever `MAX_QUADS` of them have been
+/// collected and on `flush`. The buffers are kept between frames.
+pub struct SpriteBatch {
+    mesh: Mesh,
+    /// size of the texture, to turn pixel rectangles into texture coordinates
+    texture_size: Vec2,
+}
+
+impl Default for SpriteBatch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SpriteBatch {
+    pub fn new() -> Self {
+        SpriteBatch {
+            mesh: Mesh {
+                vertices: Vec::with_capacity(MAX_QUADS * 4),
+                indices: Vec::with_capacity(MAX_QUADS * 6),
+                texture: None,
+            },
+            texture_size: Vec2::ONE,
+        }
+    }
+
+    /// Starts collecting sprites from `texture`, drawing whatever was collected from the last one.
+    pub fn begin(&mut self, texture: &Texture2D) {
+        self.flush();
+        self.texture_size = texture.size();
+        self.mesh.texture = Some(texture.clone());
+    }
+
+    /// Queues `source`, a rectangle of the texture in pixels, to be drawn `size` big centred on
+    /// `center` and turned by `rotation` radians.
+    pub fn push(&mut self, source: Rect, center: Vec2, size: Vec2, rotation: f32, color: Color) {
+        if self.mesh.vertices.len() == MAX_QUADS * 4 {
+            self.flush();
+        }
+
+        let uv = Rect::new(
+            source.x / self.texture_size.x,
+            source.y / self.texture_size.y,
+            source.w / self.texture_size.x,
+            source.h / self.texture_size.y,
+        );
+        let (sin, cos) = rotation.sin_cos();
+        let corner = |x: f32, y: f32, u: f32, v: f32| {
+            let offset = vec2(x, y) * size / 2.0;
+            let pos = center
+                + vec2(
+                    offset.x * cos - offset.y * sin,
+                    offset.x * sin + offset.y * cos,
+                );
+            Vertex::new(pos.x, pos.y, 0.0, u, v, color)
+        };
+
+        let first = self.mesh.vertices.len() as u16;
+        self.mesh.vertices.extend([
+            corner(-1.0, -1.0, uv.left(), uv.top()),
+            corner(1.0, -1.0, uv.right(), uv.top()),
+            corner(1.0, 1.0, uv.right(), uv.bottom()),
+            corner(-1.0, 1.0, uv.left(), uv.bottom()),
+        ]);
+        self.mesh
+            .indices
+            .extend([0, 1, 2, 0, 2, 3].map(|idx| first + idx));
+    }
+
+    /// Draws everything queued so far.
+    pub fn flush(&mut self) {
+        if !self.mesh.vertices.is_empty() {
+            draw_mesh(&self.mesh);
+        }
+        self.mesh.vertices.clear();
+        self.mesh.indices.clear();
+    }
+}
@@ -0,0 +1,286 @@
+//! Plays the stages over and over with simple bots and writes down how it went, for tuning prices,
+//! goals and rates without playing them all by hand.
+//!
+//! Every game is simulated without a window, a bot deciding what the pointers do on each tick.
+//! Bots are a little distracted, some games more than others, so the same bot does not play every
+//! game the same way. Games are seeded by their number, so a run can be repeated exactly.
+//!
+//! Three CSV files are written to the output directory:
+//!
+//! - `summary.csv`: win rate and time taken per stage and bot
+//! - `games.csv`: how every game ended
+//! - `money.csv`: the money and repellants of every game over time
+
+use std::{fmt::Write as _, fs, path::PathBuf};
+
+use clicker1::{
+    assets,
+    data::parse_level,
+    events::Event,
+    input::Pointer,
+    level::{rep_btn_rect, update_level, LevelState},
+    stages::Stage,
+};
+use macroquad::rand::{gen_range, srand};
+
+const USAGE: &str = "\
+usage: balance [options]
+
+  --games N       games per stage and bot, 50 by default
+  --max-ticks N   give up on a game after N ticks, 10 minutes by default
+  --every N       ticks between the samples of money.csv, 60 by default
+  --out DIR       where the CSV files go, ./balance by default";
+
+/// Decides what the pointers do on every tick.
+trait Bot {
+    fn name(&self) -> &'static str;
+
+    fn pointers(&mut self, level: &LevelState) -> Vec<Pointer>;
+}
+
+/// Every bot, made for a game with the given attention.
+const BOTS: [fn(f32) -> Box<dyn Bot>; 3] = [
+    |_| Box::new(Idle),
+    |attention| Box::new(ClickWorst::new(attention)),
+    |attention| Box::new(GreedyBuyer(ClickWorst::new(attention))),
+];
+
+/// Never clicks anything.
+struct Idle;
+
+impl Bot for Idle {
+    fn name(&self) -> &'static str {
+        "idle"
+    }
+
+    fn pointers(&mut self, _level: &LevelState) -> Vec<Pointer> {
+        vec![]
+    }
+}
+
+/// Holds the pointer on whichever object is closest to being lost, whenever it is paying
+/// attention.
+struct ClickWorst {
+    /// chance of clicking at all on a tick
+    attention: f32,
+    /// the object held on last tick
+    holding: Option<usize>,
+}
+
+impl ClickWorst {
+    fn new(attention: f32) -> Self {
+        ClickWorst {
+            attention,
+            holding: None,
+        }
+    }
+}
+
+impl Bot for ClickWorst {
+    fn name(&self) -> &'static str {
+        "click-worst"
+    }
+
+    fn pointers(&mut self, level: &LevelState) -> Vec<Pointer> {
+        if gen_range(0.0, 1.0) > self.attention {
+            self.holding = None;
+            return vec![];
+        }
+        let worst = worst_object(level);
+        let pressed = self.holding != Some(worst);
+        self.holding = Some(worst);
+        vec![Pointer {
+            pos: level.objects[worst].center(),
+            pressed,
+            down: true,
+        }]
+    }
+}
+
+/// Buys repellant as soon as there is money for it, and clicks like `ClickWorst` otherwise.
+struct GreedyBuyer(ClickWorst);
+
+impl Bot for GreedyBuyer {
+    fn name(&self) -> &'static str {
+        "greedy-buyer"
+    }
+
+    fn pointers(&mut self, level: &LevelState) -> Vec<Pointer> {
+        if level.money > level.repellant_price && gen_range(0.0, 1.0) <= self.0.attention {
+            self.0.holding = None;
+            return vec![Pointer {
+                pos: rep_btn_rect().center(),
+                pressed: true,
+                down: true,
+            }];
+        }
+        self.0.pointers(level)
+    }
+}
+
+/// The object a click helps most: the dirtiest if clicking cleans, the cleanest if it dirties.
+fn worst_object(level: &LevelState) -> usize {
+    let mut probe = level.objects[0].clone();
+    probe.dirtiness = u8::MAX / 2;
+    level.rules.on_click(&mut probe);
+    let cleans = probe.dirtiness < u8::MAX / 2;
+    let objects = level.objects.iter().enumerate();
+    let worst = match cleans {
+        true => objects.max_by_key(|(_, object)| object.dirtiness),
+        false => objects.min_by_key(|(_, object)| object.dirtiness),
+    };
+    worst.map_or(0, |(idx, _)| idx)
+}
+
+struct Options {
+    games: u64,
+    max_ticks: usize,
+    every: usize,
+    out: PathBuf,
+}
+
+impl Options {
+    fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut options = Options {
+            games: 50,
+            max_ticks: 10 * 60 * 60,
+            every: 60,
+            out: PathBuf::from("./balance"),
+        };
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let value = args.next().ok_or(format!("{arg} needs a value"))?;
+            let number = || value.parse().map_err(|_| format!("bad {arg} '{value}'"));
+            match arg.as_str() {
+                "--games" => options.games = number()? as u64,
+                "--max-ticks" => options.max_ticks = number()?,
+                "--every" => options.every = number()?.max(1),
+                "--out" => options.out = PathBuf::from(&value),
+                other => return Err(format!("unknown option '{other}'")),
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// How a single game went.
+struct Game {
+    /// won, lost or unfinished
+    end: &'static str,
+    ticks: usize,
+    money: f32,
+    repellants: u16,
+}
+
+/// Plays `level` to the end or `max_ticks`, sampling the money into `curve` every so often.
+fn play(
+    mut level: LevelState,
+    bot: &mut dyn Bot,
+    options: &Options,
+    curve: &mut Vec<(usize, f32, u16)>,
+) -> Game {
+    let (mut events, mut cues) = (vec![], vec![]);
+    let mut end = "unfinished";
+    while level.tick < options.max_ticks {
+        let pointers = bot.pointers(&level);
+        update_level(&mut level, &pointers, &mut events, &mut cues);
+        cues.clear();
+        if level.tick.is_multiple_of(options.every) {
+            curve.push((level.tick, level.money, level.repellants));
+        }
+        for event in events.drain(..) {
+            match event {
+                Event::StageWon => end = "won",
+                Event::StageLost => end = "lost",
+                _ => {}
+            }
+        }
+        if end != "unfinished" {
+            break;
+        }
+    }
+    Game {
+        end,
+        ticks: level.tick,
+        money: level.money,
+        repellants: level.repellants,
+    }
+}
+
+fn main() {
+    let options = match Options::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{err}\n\n{USAGE}");
+            std::process::exit(2);
+        }
+    };
+
+    let mut summary = String::from("stage,bot,games,win_rate,mean_ticks_to_win\n");
+    let mut games = String::from("stage,bot,game,attention,end,ticks,money,repellants\n");
+    let mut money = String::from("stage,bot,game,tick,money,repellants\n");
+    for stage in Stage::all() {
+        let path = assets::path(stage.info().level);
+        let level = match fs::read_to_string(path)
+            .map_err(|err| err.to_string())
+            .and_then(|source| parse_level(&source))
+        {
+            Ok(level) => level,
+            Err(err) => {
+                eprintln!("could not read {path}: {err}");
+                std::process::exit(1);
+            }
+        };
+
+        for make_bot in BOTS {
+            let (mut wins, mut win_ticks, mut name) = (0, 0, "");
+            for game in 0..options.games {
+                srand(game);
+                let attention = gen_range(0.5, 1.0);
+                let mut bot = make_bot(attention);
+                name = bot.name();
+                let mut curve = vec![];
+                let mut level = level.clone();
+                level.rng = game;
+                let result = play(level, bot.as_mut(), &options, &mut curve);
+                if result.end == "won" {
+                    wins += 1;
+                    win_ticks += result.ticks;
+                }
+                writeln!(
+                    games,
+                    "{stage},{name},{game},{attention},{},{},{},{}",
+                    result.end, result.ticks, result.money, result.repellants
+                )
+                .unwrap();
+                for (tick, amount, repellants) in curve {
+                    writeln!(money, "{stage},{name},{game},{tick},{amount},{repellants}").unwrap();
+                }
+            }
+            let win_rate = wins as f32 / options.games.max(1) as f32;
+            let mean_ticks = match wins {
+                0 => String::new(),
+                _ => (win_ticks / wins).to_string(),
+            };
+            let line = format!("{stage},{name},{},{win_rate},{mean_ticks}", options.games);
+            println!("{line}");
+            summary.push_str(&line);
+            summary.push('\n');
+        }
+    }
+
+    let write = |file: &str, contents: &str| {
+        let path = options.out.join(file);
+        if let Err(err) = fs::write(&path, contents) {
+            eprintln!("could not write {}: {err}", path.display());
+            std::process::exit(1);
+        }
+    };
+    if let Err(err) = fs::create_dir_all(&options.out) {
+        eprintln!("could not create {}: {err}", options.out.display());
+        std::process::exit(1);
+    }
+    write("summary.csv", &summary);
+    write("games.csv", &games);
+    write("money.csv", &money);
+}
@@ -0,0 +1,136 @@
+//! Command-line options of the native binary, mostly for testing and balancing.
+
+use clicker1::stages::Stage;
+
+pub const USAGE: &str = "\
+usage: clicker1 [options]
+
+  --stage A1|B1       start at this stage instead of the main menu
+  --skip-story        go straight from one stage to the next, without tutorials
+  --seed N            seed every stage's randomness with N
+  --level-file PATH   play PATH instead of the level file of the stage started at
+  --replay PATH       play back a replay file and check it ends the same
+  --headless          run without a window, needs --replay or --ticks
+  --ticks N           with --headless, simulate N ticks with nothing clicked
+  --windowed          start in a window, whatever the settings say
+  --fullscreen        start in fullscreen, whatever the settings say
+  --debug             draw trails, reach and numbers over the stage
+  --help              show this";
+
+#[derive(Default)]
+pub struct Options {
+    pub stage: Option<Stage>,
+    pub skip_story: bool,
+    pub seed: Option<u64>,
+    pub level_file: Option<String>,
+    pub replay: Option<String>,
+    pub headless: bool,
+    pub ticks: Option<usize>,
+    /// overrides the setting for this run
+    pub fullscreen: Option<bool>,
+    pub debug: bool,
+    pub help: bool,
+}
+
+impl Options {
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut options = Options::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = |what: &str| args.next().ok_or(format!("{arg} needs {what}"));
+            match arg.as_str() {
+                "--stage" => {
+                    let name = value("a stage")?;
+                    options.stage =
+                        Some(Stage::parse(&name).ok_or(format!("unknown stage '{name}'"))?);
+                }
+                "--skip-story" => options.skip_story = true,
+                "--seed" => {
+                    let seed = value("a number")?;
+                    options.seed = Some(seed.parse().map_err(|_| format!("bad seed '{seed}'"))?);
+                }
+                "--level-file" => options.level_file = Some(value("a path")?),
+                "--replay" => options.replay = Some(value("a path")?),
+                "--headless" => options.headless = true,
+                "--ticks" => {
+                    let ticks = value("a number")?;
+                    options.ticks = Some(
+                        ticks
+                            .parse()
+                            .map_err(|_| format!("bad tick count '{ticks}'"))?,
+                    );
+                }
+                "--windowed" => options.fullscreen = Some(false),
+                "--fullscreen" => options.fullscreen = Some(true),
+                "--debug" => options.debug = true,
+                "--help" | "-h" => options.help = true,
+                other => return Err(format!("unknown option '{other}'")),
+            }
+        }
+
+        if options.replay.is_some() && (options.stage.is_some() || options.ticks.is_some()) {
+            return Err("a replay already says which stage and how long".to_owned());
+        }
+        if options.headless && options.replay.is_none() && options.ticks.is_none() {
+            return Err("--headless needs --replay or --ticks".to_owned());
+        }
+        if options.ticks.is_some() && !options.headless {
+            return Err("--ticks only works with --headless".to_owned());
+        }
+        Ok(options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &str) -> Result<Options, String> {
+        Options::parse(args.split_whitespace().map(str::to_owned))
+    }
+
+    #[test]
+    fn reads_every_option() {
+        let options = parse("--stage B1 --skip-story --seed 7 --level-file x.txt --debug").unwrap();
+        assert_eq!(options.stage, Stage::parse("B1"));
+        assert!(options.skip_story && options.debug);
+        assert_eq!(options.seed, Some(7));
+        assert_eq!(options.level_file.as_deref(), Some("x.txt"));
+        let options = parse("--headless --ticks 600 --windowed").unwrap();
+        assert!(options.headless);
+        assert_eq!(
+            (options.ticks, options.fullscreen),
+            (Some(600), Some(false))
+        );
+    }
+
+    #[test]
+    fn a_replay_says_where_and_how_long_by_itself() {
+        assert!(parse("--replay r.txt").is_ok());
+        assert!(parse("--replay r.txt --headless").is_ok());
+        assert!(parse("--replay r.txt --stage A1").is_err());
+        assert!(parse("--replay r.txt --headless --ticks 10").is_err());
+    }
+
+    #[test]
+    fn headless_runs_need_something_to_run() {
+        assert!(parse("--headless").is_err());
+        assert!(parse("--headless --ticks 10").is_ok());
+        assert!(parse("--headless --stage B1 --ticks 10").is_ok());
+    }
+
+    #[test]
+    fn ticks_only_go_with_headless() {
+        assert!(parse("--ticks 10").is_err());
+        assert!(parse("--stage A1 --ticks 10").is_err());
+    }
+
+    #[test]
+    fn bad_values_are_errors() {
+        assert!(parse("--stage C9").is_err());
+        assert!(parse("--seed many").is_err());
+        assert!(parse("--headless --ticks -1").is_err());
+        assert!(parse("--replay").is_err());
+        assert!(parse("--sneaky").is_err());
+    }
+}
@@ -0,0 +1,302 @@
+//! Parsers for the level files in `levels/` and the story files in `story/`.
+
+use std::str::FromStr;
+
+use macroquad::math::vec2;
+
+use crate::{
+    assets::{self, AssetKind, ResourceName},
+    effects::Effect,
+    incidents::{Impact, Incident},
+    level::{ant_grid, DirtyObj, LevelState, Trail},
+    rules,
+    story::StoryIter,
+    util::{LineSegment, Path},
+};
+
+fn parse<T: FromStr>(value: Option<&str>, what: &str) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("missing {what}"))?;
+    value
+        .parse()
+        .map_err(|_| format!("'{value}' is not a valid {what}"))
+}
+
+fn parse_segment<'a>(mut values: impl Iterator<Item = &'a str>) -> Result<LineSegment, String> {
+    let mut coord = || parse::<f32>(values.next(), "coordinate");
+    Ok(LineSegment::new((coord()?, coord()?), (coord()?, coord()?)))
+}
+
+/// `path <width> x1 y1 x2 y2 ...` or `curve <width> x0 y0 x1 y1 x2 y2 x3 y3`.
+fn parse_path<'a>(key: &str, mut values: impl Iterator<Item = &'a str>) -> Result<Path, String> {
+    let width = parse(values.next(), "width")?;
+    let coords = values
+        .map(|value| parse::<f32>(Some(value), "coordinate"))
+        .collect::<Result<Vec<_>, _>>()?;
+    let points: Vec<_> = coords
+        .chunks_exact(2)
+        .map(|xy| vec2(xy[0], xy[1]))
+        .collect();
+    match (key, points.as_slice()) {
+        _ if coords.len() % 2 == 1 => Err("coordinates have to come in pairs".to_owned()),
+        ("curve", &[p0, p1, p2, p3]) => Ok(Path::bezier(p0, p1, p2, p3, width)),
+        ("curve", _) => Err("a curve needs exactly 4 points".to_owned()),
+        (_, []) => Err("a path needs at least 1 point".to_owned()),
+        _ => Ok(Path::polyline(points, width)),
+    }
+}
+
+/// An `object` line waiting for its `start` and `end` segments.
+type PendingObject = Option<(u8, usize, u8, Option<LineSegment>)>;
+
+/// Builds the starting state of a stage from its level file.
+pub fn parse_level(source: &str) -> Result<LevelState, String> {
+    let mut level = LevelState {
+        tick: 0,
+        objects: vec![],
+        rules: &rules::Easy,
+        money: 0.0,
+        money_goal: 0.0,
+        repellants: 0,
+        repellant_name: String::new(),
+        repellant_price: 0.0,
+        effects: vec![],
+        ant_size: 8.0,
+        corpses: vec![],
+        ants: ant_grid(),
+        incidents: vec![],
+        ongoing: vec![],
+        rng: 0,
+    };
+    let mut object = None;
+
+    for (idx, line) in source.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        parse_level_line(&mut level, &mut object, line)
+            .map_err(|err| format!("line {}: {err}", idx + 1))?;
+    }
+
+    if object.is_some() {
+        return Err("last object is missing its segments".to_owned());
+    }
+    // shown as soon as the stage is drawn, which can be before its first tick
+    level.index_ants();
+    Ok(level)
+}
+
+fn parse_level_line(
+    level: &mut LevelState,
+    object: &mut PendingObject,
+    line: &str,
+) -> Result<(), String> {
+    let mut values = line.split_whitespace();
+    let key = values.next().unwrap_or_default();
+    match key {
+        "difficulty" => {
+            let name = values.next().unwrap_or_default();
+            level.rules =
+                rules::by_name(name).ok_or_else(|| format!("unknown difficulty '{name}'"))?;
+        }
+        "money" => level.money = parse(values.next(), "amount of money")?,
+        "money_goal" => level.money_goal = parse(values.next(), "amount of money")?,
+        "repellants" => level.repellants = parse(values.next(), "number of repellants")?,
+        "repellant_name" => level.repellant_name = values.collect::<Vec<_>>().join(" "),
+        "repellant_price" => level.repellant_price = parse(values.next(), "price")?,
+        "ant_size" => level.ant_size = parse(values.next(), "size")?,
+        "effects" => {
+            level.effects = values
+                .map(|name| Effect::parse(name).ok_or_else(|| format!("unknown effect '{name}'")))
+                .collect::<Result<_, _>>()?;
+        }
+        "incident" => {
+            let chance = parse(values.next(), "chance")?;
+            let seconds: f32 = parse(values.next(), "duration")?;
+            let impact = Impact::parse(
+                values.next().unwrap_or_default(),
+                values.next().unwrap_or_default(),
+            )?;
+            level.incidents.push(Incident {
+                chance,
+                ticks: (seconds * 60.0) as usize,
+                impact,
+                announcement: values.collect::<Vec<_>>().join(" "),
+            });
+        }
+        "object" => {
+            if object.is_some() {
+                return Err("previous object is missing its segments".to_owned());
+            }
+            *object = Some((
+                parse(values.next(), "dirtiness")?,
+                parse(values.next(), "amount of ants")?,
+                parse(values.next(), "chance")?,
+                None,
+            ));
+        }
+        "start" => match object {
+            Some((_, _, _, start @ None)) => *start = Some(parse_segment(values)?),
+            _ => return Err("'start' has to come right after 'object'".to_owned()),
+        },
+        "end" => match object.take() {
+            Some((dirtiness, amount, chance_bidir, Some(start))) => {
+                let end = parse_segment(values)?;
+                let trail = Trail::Between(start, end);
+                level
+                    .objects
+                    .push(DirtyObj::new(dirtiness, trail, amount, chance_bidir));
+            }
+            _ => return Err("'end' has to come right after 'start'".to_owned()),
+        },
+        "path" | "curve" => match object.take() {
+            Some((dirtiness, amount, chance_bidir, None)) => {
+                let trail = Trail::Along(parse_path(key, values)?);
+                level
+                    .objects
+                    .push(DirtyObj::new(dirtiness, trail, amount, chance_bidir));
+            }
+            _ => return Err(format!("'{key}' has to come right after 'object'")),
+        },
+        _ => return Err(format!("unknown key '{key}'")),
+    }
+    Ok(())
+}
+
+/// Splits a story file into pages. Pages are separated by blank lines and `@ Name` sets the
+/// background of every page after it.
+pub fn parse_story(name: ResourceName, source: &str) -> Result<StoryIter, String> {
+    let mut pages = vec![];
+    let mut background = None;
+    let mut page: Vec<String> = vec![];
+
+    for (idx, line) in source.lines().enumerate() {
+        let line = line.trim_end();
+        if line.starts_with('#') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('@') {
+            let name = name.trim();
+            let resource = ResourceName::parse(name)
+                .ok_or_else(|| format!("line {}: unknown resource '{name}'", idx + 1))?;
+            // stories are drawn over a full texture, nothing else can be shown there
+            if assets::kind(resource) != AssetKind::Texture {
+                return Err(format!("line {}: '{name}' is not an image", idx + 1));
+            }
+            background = Some(resource);
+            continue;
+        }
+        if !line.is_empty() {
+            page.push(line.to_owned());
+            continue;
+        }
+        if !page.is_empty() {
+            let background =
+                background.ok_or_else(|| format!("line {}: page has no background", idx + 1))?;
+            pages.push((background, std::mem::take(&mut page)));
+        }
+    }
+    if !page.is_empty() {
+        let background = background.ok_or("last page has no background")?;
+        pages.push((background, page));
+    }
+
+    Ok(StoryIter {
+        source: name,
+        pages: pages.into(),
+        revealed: 0.0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{level, OBJECT};
+
+    fn path(line: &str) -> Result<Path, String> {
+        let mut values = line.split_whitespace();
+        let key = values.next().unwrap();
+        parse_path(key, values)
+    }
+
+    #[test]
+    fn paths_need_whole_points() {
+        assert!(path("path 4 0 0 10 10").is_ok());
+        assert!(path("path 4 0 0").is_ok());
+        assert!(path("path 4 0 0 10").is_err());
+        assert!(path("path 4").is_err());
+        assert!(path("path").is_err());
+    }
+
+    #[test]
+    fn curves_need_four_points() {
+        assert!(path("curve 4 0 0 10 0 10 10 0 10").is_ok());
+        assert!(path("curve 4 0 0 10 0 10 10").is_err());
+        assert!(path("curve 4 0 0 10 0 10 10 0 10 5 5").is_err());
+        assert!(path("curve 4 0 0 10 0 10 10 0").is_err());
+    }
+
+    #[test]
+    fn ants_show_up_before_the_first_tick() {
+        let level = level(&format!("difficulty easy\nmoney_goal 1000\n{OBJECT}"));
+        assert_eq!(level.tick, 0);
+        assert!(!level.ants.is_empty());
+    }
+
+    fn story(source: &str) -> Result<StoryIter, String> {
+        parse_story(ResourceName::StoryIntro, source)
+    }
+
+    #[test]
+    fn level_errors_say_where_they_are() {
+        let header = "difficulty easy\nmoney_goal 1000\n";
+        let err = |source: &str| parse_level(source).err().unwrap();
+        assert_eq!(
+            err(&format!("{header}colour red\n")),
+            "line 3: unknown key 'colour'"
+        );
+        assert_eq!(
+            err(&format!("{header}start 0 0 10 0\n")),
+            "line 3: 'start' has to come right after 'object'"
+        );
+        assert_eq!(
+            err("object 100 10 0\nobject 100 10 0\n"),
+            "line 2: previous object is missing its segments"
+        );
+        assert_eq!(
+            err("object 100 10 0\nstart 0 0 10 0\n"),
+            "last object is missing its segments"
+        );
+        assert_eq!(
+            err("difficulty hard\n"),
+            "line 1: unknown difficulty 'hard'"
+        );
+        assert!(parse_level(&format!("{header}{OBJECT}")).is_ok());
+    }
+
+    #[test]
+    fn every_page_needs_an_image_behind_it() {
+        let pages = story("@ Story1\nOnce upon a time.\n\n@ Story2\nThe end.\n").unwrap();
+        assert_eq!(pages.pages.len(), 2);
+        assert_eq!(
+            story("Once upon a time.\n\nThe end.\n").err().unwrap(),
+            "line 2: page has no background"
+        );
+        assert_eq!(
+            story("Once upon a time.\n").err().unwrap(),
+            "last page has no background"
+        );
+        assert_eq!(
+            story("@ Nothing\nOnce upon a time.\n").err().unwrap(),
+            "line 1: unknown resource 'Nothing'"
+        );
+        assert_eq!(
+            story("@ Squish\nOnce upon a time.\n").err().unwrap(),
+            "line 1: 'Squish' is not an image"
+        );
+        assert_eq!(
+            story("@ LevelA1\nOnce upon a time.\n").err().unwrap(),
+            "line 1: 'LevelA1' is not an image"
+        );
+    }
+}
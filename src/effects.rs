@@ -0,0 +1,185 @@
+use std::f32::consts::PI;
+
+use macroquad::{
+    color::Color,
+    math::{vec2, Vec2},
+};
+use macroquad_particles::{ColorCurve, EmissionShape, Emitter, EmitterConfig, EmittersCache};
+
+/// Particle effects a stage can opt into, see `LevelState::effects`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Effect {
+    /// bits of ant where the player cleans an object
+    Splat,
+    /// sparkles on the buy button after a purchase
+    Coins,
+    /// a cloud over an object that went completely dirty
+    Dust,
+    /// trails behind the drones
+    Exhaust,
+}
+
+impl Effect {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "Splat" => Some(Effect::Splat),
+            "Coins" => Some(Effect::Coins),
+            "Dust" => Some(Effect::Dust),
+            "Exhaust" => Some(Effect::Exhaust),
+            _ => None,
+        }
+    }
+}
+
+// a held click on the 700-ant keyboard asks for a splat nearly every frame, anything past this
+// in a single frame is dropped rather than piling up emitters
+const MAX_SPAWNS_PER_FRAME: usize = 4;
+
+/// Pools the emitters for every effect so they are only ever allocated once.
+pub struct Effects {
+    splat: EmittersCache,
+    coins: EmittersCache,
+    dust: EmittersCache,
+    /// one per drone, these emit continuously
+    exhaust: Vec<Emitter>,
+    spawned: usize,
+}
+
+impl Default for Effects {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Effects {
+    pub fn new() -> Self {
+        Effects {
+            splat: EmittersCache::new(splat()),
+            coins: EmittersCache::new(coins()),
+            dust: EmittersCache::new(dust()),
+            exhaust: vec![],
+            spawned: 0,
+        }
+    }
+
+    pub fn spawn(&mut self, effect: Effect, pos: Vec2) {
+        if self.spawned >= MAX_SPAWNS_PER_FRAME {
+            return;
+        }
+        self.spawned += 1;
+        match effect {
+            Effect::Splat => self.splat.spawn(pos),
+            Effect::Coins => self.coins.spawn(pos),
+            Effect::Dust => self.dust.spawn(pos),
+            // trails are continuous, they follow whatever is passed to `draw`
+            Effect::Exhaust => {}
+        }
+    }
+
+    /// Draws everything spawned so far plus an exhaust trail behind each of `drones`, moving the
+    /// particles on by a frame. They stand still for as long as this is not called.
+    pub fn draw(&mut self, drones: &[Vec2]) {
+        while self.exhaust.len() < drones.len() {
+            self.exhaust.push(Emitter::new(exhaust()));
+        }
+        for (emitter, drone) in self.exhaust.iter_mut().zip(drones) {
+            emitter.draw(*drone);
+        }
+
+        self.splat.draw();
+        self.coins.draw();
+        self.dust.draw();
+        self.spawned = 0;
+    }
+}
+
+fn splat() -> EmitterConfig {
+    EmitterConfig {
+        one_shot: true,
+        emitting: false,
+        lifetime: 0.35,
+        lifetime_randomness: 0.3,
+        amount: 10,
+        explosiveness: 1.0,
+        initial_direction_spread: 2.0 * PI,
+        initial_velocity: 60.0,
+        initial_velocity_randomness: 0.5,
+        size: 2.5,
+        size_randomness: 0.4,
+        colors_curve: ColorCurve {
+            start: Color::new(0.25, 0.1, 0.05, 1.0),
+            mid: Color::new(0.4, 0.15, 0.05, 0.8),
+            end: Color::new(0.4, 0.15, 0.05, 0.0),
+        },
+        ..Default::default()
+    }
+}
+
+fn coins() -> EmitterConfig {
+    EmitterConfig {
+        one_shot: true,
+        emitting: false,
+        lifetime: 0.6,
+        lifetime_randomness: 0.3,
+        amount: 14,
+        explosiveness: 0.9,
+        emission_shape: EmissionShape::Rect {
+            width: 120.0,
+            height: 10.0,
+        },
+        initial_direction: vec2(0.0, -1.0),
+        initial_direction_spread: 0.8,
+        initial_velocity: 140.0,
+        initial_velocity_randomness: 0.4,
+        gravity: vec2(0.0, 400.0),
+        size: 3.0,
+        colors_curve: ColorCurve {
+            start: Color::new(1.0, 0.95, 0.5, 1.0),
+            mid: Color::new(1.0, 0.8, 0.2, 1.0),
+            end: Color::new(1.0, 0.7, 0.1, 0.0),
+        },
+        ..Default::default()
+    }
+}
+
+fn dust() -> EmitterConfig {
+    EmitterConfig {
+        one_shot: true,
+        emitting: false,
+        lifetime: 1.2,
+        lifetime_randomness: 0.4,
+        amount: 30,
+        explosiveness: 0.8,
+        emission_shape: EmissionShape::Sphere { radius: 25.0 },
+        initial_direction: vec2(0.0, -1.0),
+        initial_direction_spread: PI,
+        initial_velocity: 15.0,
+        initial_velocity_randomness: 0.8,
+        size: 7.0,
+        size_randomness: 0.5,
+        colors_curve: ColorCurve {
+            start: Color::new(0.5, 0.45, 0.4, 0.6),
+            mid: Color::new(0.45, 0.4, 0.35, 0.4),
+            end: Color::new(0.4, 0.35, 0.3, 0.0),
+        },
+        ..Default::default()
+    }
+}
+
+fn exhaust() -> EmitterConfig {
+    EmitterConfig {
+        lifetime: 0.5,
+        amount: 20,
+        initial_direction: vec2(0.0, 1.0),
+        initial_direction_spread: 0.6,
+        initial_velocity: 20.0,
+        size: 2.0,
+        size_randomness: 0.5,
+        colors_curve: ColorCurve {
+            start: Color::new(0.8, 0.8, 0.8, 0.7),
+            mid: Color::new(0.6, 0.6, 0.6, 0.4),
+            end: Color::new(0.5, 0.5, 0.5, 0.0),
+        },
+        ..Default::default()
+    }
+}
@@ -0,0 +1,98 @@
+//! What came out of a tick of a stage, for everything outside of the rules to react to: sounds,
+//! particles, the next screen and tests.
+//!
+//! `update_level` pushes onto a queue that lives for one frame. Feedback on the pointer itself,
+//! like the squish of a click, is not an event but a `Cue`.
+
+use crate::assets::ResourceName;
+
+/// Shares of the money goal that are worth telling about, in percent.
+pub const MONEY_MILESTONES: [u8; 3] = [25, 50, 75];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Event {
+    /// object `n` got dirty enough for its critical ants to show up
+    ObjectWarned(usize),
+    /// object `n` is completely dirty
+    ObjectCritical(usize),
+    /// a repellant was bought for this much
+    Purchased(f32),
+    /// the money went past this many percent of the goal
+    MoneyMilestone(u8),
+    /// incident `n` of the level started
+    IncidentStarted(usize),
+    /// incident `n` of the level, which lasts a while, is over
+    IncidentOver(usize),
+    StageWon,
+    /// comes right before `StageLost` when it was the time that ran out
+    ShiftEnded,
+    StageLost,
+}
+
+impl Event {
+    /// What is heard when it happens.
+    pub fn sound(&self) -> Option<ResourceName> {
+        match self {
+            Event::ObjectWarned(_) => Some(ResourceName::Warn),
+            Event::Purchased(_) => Some(ResourceName::Cash),
+            Event::IncidentStarted(_) => Some(ResourceName::Warn),
+            Event::StageWon => Some(ResourceName::Win),
+            Event::StageLost => Some(ResourceName::Lose),
+            Event::ObjectCritical(_)
+            | Event::MoneyMilestone(_)
+            | Event::IncidentOver(_)
+            | Event::ShiftEnded => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        level::{MAX_DIRTINESS, WARN_DIRTINESS},
+        testing::{level, run, OBJECT},
+    };
+
+    #[test]
+    fn dirt_coming_in_is_warned_about_once() {
+        let mut level = level(&format!(
+            "difficulty easy\nmoney_goal 1000000\n{}",
+            OBJECT.replacen("100", &WARN_DIRTINESS.to_string(), 1)
+        ));
+        let events = run(
+            &mut level,
+            7 * (MAX_DIRTINESS - WARN_DIRTINESS + 2) as usize,
+        );
+        let warned = events
+            .iter()
+            .filter(|event| **event == Event::ObjectWarned(0))
+            .count();
+        assert_eq!(warned, 1);
+        assert!(events.contains(&Event::ObjectCritical(0)));
+        assert_eq!(events.last(), Some(&Event::StageLost));
+    }
+
+    #[test]
+    fn idle_money_passes_every_milestone_then_wins() {
+        let mut level = level(&format!("difficulty easy\nmoney_goal 10\n{OBJECT}"));
+        let events = run(&mut level, 200);
+        let milestones: Vec<_> = events
+            .iter()
+            .filter_map(|event| match event {
+                Event::MoneyMilestone(percent) => Some(*percent),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(milestones, MONEY_MILESTONES);
+        assert_eq!(events.last(), Some(&Event::StageWon));
+    }
+
+    #[test]
+    fn running_out_of_time_ends_the_shift() {
+        let mut level = level(&format!("difficulty medium\nmoney_goal 1000000\n{OBJECT}"));
+        level.tick = level.rules.shift_length().unwrap() - 5;
+        let events = run(&mut level, 10);
+        assert_eq!(events, [Event::ShiftEnded, Event::StageLost]);
+    }
+}
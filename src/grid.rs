@@ -0,0 +1,202 @@
+//! A uniform grid for finding things by position without looking at all of them.
+
+use macroquad::math::{Rect, Vec2};
+
+/// Buckets items by the cell of a fixed-size grid their position falls in.
+///
+/// The grid is rebuilt from scratch whenever the positions change, which for ants is every tick:
+/// `clear`, `insert` everything, then `build`. The buffers are kept between rebuilds so doing
+/// that does not allocate once they have grown large enough. Positions outside of `bounds` go
+/// into the nearest edge cell, so they are still found.
+#[derive(Clone)]
+pub struct Grid<T> {
+    bounds: Rect,
+    cell_size: f32,
+    columns: usize,
+    rows: usize,
+    /// items inserted since the last `build`, with their cell
+    pending: Vec<(usize, Vec2, T)>,
+    /// every item sorted by cell, the items of cell `n` are `items[starts[n]..starts[n + 1]]`
+    items: Vec<(Vec2, T)>,
+    starts: Vec<usize>,
+}
+
+impl<T: Copy> Grid<T> {
+    pub fn new(bounds: Rect, cell_size: f32) -> Self {
+        let columns = (bounds.w / cell_size).ceil().max(1.0) as usize;
+        let rows = (bounds.h / cell_size).ceil().max(1.0) as usize;
+        Grid {
+            bounds,
+            cell_size,
+            columns,
+            rows,
+            pending: vec![],
+            items: vec![],
+            starts: vec![0; columns * rows + 1],
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.pending.clear();
+    }
+
+    /// Queues `item` for the next `build`, queries do not see it until then.
+    pub fn insert(&mut self, pos: Vec2, item: T) {
+        let (column, row) = self.cell(pos);
+        self.pending.push((row * self.columns + column, pos, item));
+    }
+
+    /// Replaces what the grid holds with everything inserted since the last `clear`.
+    pub fn build(&mut self) {
+        self.starts.fill(0);
+        for (cell, _, _) in &self.pending {
+            self.starts[cell + 1] += 1;
+        }
+        for cell in 1..self.starts.len() {
+            self.starts[cell] += self.starts[cell - 1];
+        }
+
+        self.items.clear();
+        if let Some(&(_, pos, item)) = self.pending.first() {
+            self.items.resize(self.pending.len(), (pos, item));
+        }
+        // `starts` is used as the next free slot of each cell while filling in, which leaves it
+        // shifted by one cell at the end
+        for &(cell, pos, item) in &self.pending {
+            self.items[self.starts[cell]] = (pos, item);
+            self.starts[cell] += 1;
+        }
+        self.starts.rotate_right(1);
+        self.starts[0] = 0;
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Everything within `radius` of `center`.
+    pub fn within(&self, center: Vec2, radius: f32) -> impl Iterator<Item = (Vec2, T)> + '_ {
+        let area = Rect::new(
+            center.x - radius,
+            center.y - radius,
+            radius * 2.0,
+            radius * 2.0,
+        );
+        self.cells_over(area)
+            .filter(move |(pos, _)| pos.distance_squared(center) <= radius * radius)
+    }
+
+    /// The closest item within `radius` of `center`.
+    pub fn nearest(&self, center: Vec2, radius: f32) -> Option<(Vec2, T)> {
+        self.within(center, radius).min_by(|(a, _), (b, _)| {
+            a.distance_squared(center)
+                .total_cmp(&b.distance_squared(center))
+        })
+    }
+
+    /// Everything inside `area`, for culling.
+    pub fn inside(&self, area: Rect) -> impl Iterator<Item = (Vec2, T)> + '_ {
+        self.cells_over(area)
+            .filter(move |(pos, _)| area.contains(*pos))
+    }
+
+    /// Everything in the cells `area` touches, some of which will be outside of it.
+    fn cells_over(&self, area: Rect) -> impl Iterator<Item = (Vec2, T)> + '_ {
+        let (left, top) = self.cell(area.point());
+        let (right, bottom) = self.cell(area.point() + area.size());
+        (top..=bottom).flat_map(move |row| {
+            let start = self.starts[row * self.columns + left];
+            let end = self.starts[row * self.columns + right + 1];
+            self.items[start..end].iter().copied()
+        })
+    }
+
+    fn cell(&self, pos: Vec2) -> (usize, usize) {
+        let local = (pos - self.bounds.point()) / self.cell_size;
+        // `as` saturates, which takes care of anything left of or above the bounds
+        (
+            (local.x as usize).min(self.columns - 1),
+            (local.y as usize).min(self.rows - 1),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use macroquad::math::vec2;
+    use proptest::prelude::*;
+
+    use super::*;
+
+    const BOUNDS: Rect = Rect {
+        x: 0.0,
+        y: 0.0,
+        w: 960.0,
+        h: 540.0,
+    };
+
+    fn positions() -> impl Strategy<Value = Vec<Vec2>> {
+        // some of them off the edges
+        prop::collection::vec((-100.0f32..1060.0, -100.0f32..640.0), 0..300)
+            .prop_map(|points| points.into_iter().map(|(x, y)| vec2(x, y)).collect())
+    }
+
+    fn grid(positions: &[Vec2]) -> Grid<usize> {
+        let mut grid = Grid::new(BOUNDS, 24.0);
+        for (idx, pos) in positions.iter().enumerate() {
+            grid.insert(*pos, idx);
+        }
+        grid.build();
+        grid
+    }
+
+    proptest! {
+        #[test]
+        fn within_finds_what_a_scan_finds(
+            positions in positions(),
+            x in -50.0f32..1010.0,
+            y in -50.0f32..590.0,
+            radius in 0.0f32..100.0,
+        ) {
+            let center = vec2(x, y);
+            let mut found: Vec<_> = grid(&positions).within(center, radius).map(|(_, idx)| idx).collect();
+            found.sort();
+            let expected: Vec<_> = (0..positions.len())
+                .filter(|idx| positions[*idx].distance_squared(center) <= radius * radius)
+                .collect();
+            prop_assert_eq!(found, expected);
+        }
+
+        #[test]
+        fn inside_finds_what_a_scan_finds(
+            positions in positions(),
+            x in -50.0f32..1010.0,
+            y in -50.0f32..590.0,
+            w in 0.0f32..500.0,
+            h in 0.0f32..500.0,
+        ) {
+            let area = Rect::new(x, y, w, h);
+            let mut found: Vec<_> = grid(&positions).inside(area).map(|(_, idx)| idx).collect();
+            found.sort();
+            let expected: Vec<_> = (0..positions.len())
+                .filter(|idx| area.contains(positions[*idx]))
+                .collect();
+            prop_assert_eq!(found, expected);
+        }
+
+        #[test]
+        fn rebuilding_forgets_the_old_items(first in positions(), second in positions()) {
+            let mut grid = grid(&first);
+            grid.clear();
+            for (idx, pos) in second.iter().enumerate() {
+                grid.insert(*pos, idx);
+            }
+            grid.build();
+            prop_assert_eq!(grid.len(), second.len());
+        }
+    }
+}
@@ -0,0 +1,74 @@
+//! Picks up files of the manifest that were edited while the game runs, in debug builds only.
+
+use crate::assets::{AssetKind, ResourceName};
+
+/// Manifest entries, as handed out by `Watcher::changed`.
+pub type Entry = (ResourceName, AssetKind, &'static str);
+
+#[cfg(all(debug_assertions, not(target_arch = "wasm32")))]
+mod watch {
+    use std::{fs, time::SystemTime};
+
+    use super::Entry;
+    use crate::assets::MANIFEST;
+
+    // checking the whole manifest every frame is wasteful, twice a second is quick enough
+    const POLL_FRAMES: usize = 30;
+
+    /// Polls the modification time of every file in the manifest.
+    pub struct Watcher {
+        frame: usize,
+        modified: Vec<Option<SystemTime>>,
+    }
+
+    impl Watcher {
+        pub fn new() -> Self {
+            Watcher {
+                frame: 0,
+                modified: MANIFEST.iter().map(|(_, _, path)| modified(path)).collect(),
+            }
+        }
+
+        /// Entries whose file changed since the last call that found any.
+        pub fn changed(&mut self) -> Vec<Entry> {
+            self.frame += 1;
+            if !self.frame.is_multiple_of(POLL_FRAMES) {
+                return vec![];
+            }
+            let mut changed = vec![];
+            for (entry, last) in MANIFEST.iter().zip(self.modified.iter_mut()) {
+                let now = modified(entry.2);
+                // a file being written can be missing for a moment, wait until it is back
+                if now.is_some() && now != *last {
+                    *last = now;
+                    changed.push(*entry);
+                }
+            }
+            changed
+        }
+    }
+
+    fn modified(path: &str) -> Option<SystemTime> {
+        fs::metadata(path).and_then(|meta| meta.modified()).ok()
+    }
+}
+
+#[cfg(not(all(debug_assertions, not(target_arch = "wasm32"))))]
+mod watch {
+    use super::Entry;
+
+    /// Never sees a change, files are only read once outside of debug builds.
+    pub struct Watcher;
+
+    impl Watcher {
+        pub fn new() -> Self {
+            Watcher
+        }
+
+        pub fn changed(&mut self) -> Vec<Entry> {
+            vec![]
+        }
+    }
+}
+
+pub use watch::Watcher;
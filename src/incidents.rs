@@ -0,0 +1,197 @@
+//! Things that happen to a stage by chance while it is played, like a sugar spill or a neighbour
+//! spraying. Level files define them with `incident` lines:
+//!
+//! ```text
+//! incident <chance per minute> <seconds> <impact> <amount> <announcement...>
+//! ```
+//!
+//! The chances are drawn from `LevelState::rng`, which is seeded like everything else random when
+//! the stage starts, so a replay has the same incidents happen at the same ticks.
+
+use crate::{events::Event, level::LevelState};
+
+/// What an incident does to the stage.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Impact {
+    /// one object gets this much dirtier at once
+    DirtyOne(u8),
+    /// the dirtiness of every object is multiplied by this at once
+    ScaleAll(f32),
+    /// the bounds of one object move this much closer together on both sides, while it lasts.
+    /// they get there over `EASE_IN_TICKS`, so there is time to react to the announcement
+    TightenOne(u8),
+}
+
+impl Impact {
+    /// `name amount`, as written in level files.
+    pub fn parse(name: &str, amount: &str) -> Result<Self, String> {
+        let bad_amount = || format!("'{amount}' is not a valid amount for {name}");
+        match name {
+            "dirty_one" => Ok(Impact::DirtyOne(amount.parse().map_err(|_| bad_amount())?)),
+            "scale_all" => Ok(Impact::ScaleAll(amount.parse().map_err(|_| bad_amount())?)),
+            "tighten_one" => Ok(Impact::TightenOne(
+                amount.parse().map_err(|_| bad_amount())?,
+            )),
+            _ => Err(format!("unknown impact '{name}'")),
+        }
+    }
+
+    /// Whether it picks an object to happen to.
+    fn picks_object(self) -> bool {
+        match self {
+            Impact::DirtyOne(_) | Impact::TightenOne(_) => true,
+            Impact::ScaleAll(_) => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Incident {
+    /// how likely it is to start in any given minute it is not already going on
+    pub chance: f32,
+    /// ticks it lasts, 0 for the ones that are over as soon as they happen
+    pub ticks: usize,
+    pub impact: Impact,
+    /// shown on screen as it starts
+    pub announcement: String,
+}
+
+/// How long an incident takes to have its full impact, for the ones that last.
+pub const EASE_IN_TICKS: usize = 3 * 60;
+
+/// An incident with a duration that started and is not over yet.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ongoing {
+    /// which of `LevelState::incidents`
+    pub incident: usize,
+    /// the object it happens to, if it picks one
+    pub object: usize,
+    pub ticks_left: usize,
+}
+
+impl Ongoing {
+    /// How much of its impact `incident`, the one going on, has by now: 0 as it starts, up to 1
+    /// once `EASE_IN_TICKS` went by.
+    pub fn eased_in(&self, incident: &Incident) -> f32 {
+        let elapsed = incident.ticks.saturating_sub(self.ticks_left);
+        (elapsed as f32 / EASE_IN_TICKS as f32).min(1.0)
+    }
+}
+
+/// Starts incidents as the chances come up and ends the ones that ran out, called on every tick.
+pub fn update(level: &mut LevelState, events: &mut Vec<Event>) {
+    level.ongoing.retain_mut(|ongoing| {
+        ongoing.ticks_left -= 1;
+        if ongoing.ticks_left == 0 {
+            events.push(Event::IncidentOver(ongoing.incident));
+        }
+        ongoing.ticks_left > 0
+    });
+
+    for idx in 0..level.incidents.len() {
+        let incident = &level.incidents[idx];
+        let (chance, impact, ticks) = (incident.chance / 3600.0, incident.impact, incident.ticks);
+        if level.ongoing.iter().any(|ongoing| ongoing.incident == idx)
+            || random(&mut level.rng) >= chance
+            || (impact.picks_object() && level.objects.is_empty())
+        {
+            continue;
+        }
+        let object = match impact.picks_object() {
+            true => (random(&mut level.rng) * level.objects.len() as f32) as usize,
+            false => 0,
+        };
+        match impact {
+            Impact::DirtyOne(by) => {
+                let object = &mut level.objects[object];
+                object.dirtiness = object.dirtiness.saturating_add(by);
+            }
+            Impact::ScaleAll(by) => {
+                for object in &mut level.objects {
+                    object.dirtiness = (object.dirtiness as f32 * by).clamp(0.0, 255.0) as u8;
+                }
+            }
+            Impact::TightenOne(_) => {}
+        }
+        events.push(Event::IncidentStarted(idx));
+        if ticks > 0 {
+            level.ongoing.push(Ongoing {
+                incident: idx,
+                object,
+                ticks_left: ticks,
+            });
+        }
+    }
+}
+
+/// The next number from 0 to 1 out of `state`, which is moved along (splitmix64).
+fn random(state: &mut u64) -> f32 {
+    *state = state.wrapping_add(0x9E3779B97F4A7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^= z >> 31;
+    (z >> 40) as f32 / (1u64 << 24) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{level, run, OBJECT};
+
+    /// Two objects that are all the same, to see which one an incident picked. They start in the
+    /// middle of the bounds, to stay within them for a while.
+    fn houses(incident: &str) -> LevelState {
+        let house = OBJECT.replacen("100", "120", 1);
+        level(&format!(
+            "difficulty medium\nmoney_goal 1000\n{house}{house}incident {incident}\n"
+        ))
+    }
+
+    #[test]
+    fn a_certain_incident_lasts_its_time_and_tightens_the_bounds() {
+        let mut level = houses("1000000 5 tighten_one 30 Somebody came home.");
+        let events = run(&mut level, 1);
+        assert_eq!(events, [Event::IncidentStarted(0)]);
+        let object = level.ongoing[0].object;
+        assert_eq!(level.dirtiness_bounds(object), (40, 200));
+        run(&mut level, EASE_IN_TICKS / 2);
+        assert_eq!(level.dirtiness_bounds(object), (55, 185));
+        run(&mut level, EASE_IN_TICKS / 2);
+        assert_eq!(level.dirtiness_bounds(object), (70, 170));
+        assert_eq!(level.dirtiness_bounds(1 - object), (40, 200));
+
+        // over after five seconds, starting again right away
+        let events = run(&mut level, 5 * 60 - EASE_IN_TICKS);
+        assert_eq!(
+            events[events.len() - 2..],
+            [Event::IncidentOver(0), Event::IncidentStarted(0)]
+        );
+    }
+
+    #[test]
+    fn the_same_seed_has_the_same_incidents() {
+        let mut levels = [0, 0, 1].map(|seed| {
+            let mut level = houses("30 0 dirty_one 10 Sugar!");
+            level.rng = seed;
+            level
+        });
+        let [a, b, c] = levels.each_mut().map(|level| run(level, 3600));
+        assert!(!a.is_empty());
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn tightening_leaves_time_to_react() {
+        let house = OBJECT.replacen("100", "190", 1);
+        let mut level = level(&format!(
+            "difficulty medium\nmoney_goal 1000\n{house}{house}\
+            incident 1000000 10 tighten_one 25 A resident came home.\n"
+        ));
+        let events = run(&mut level, EASE_IN_TICKS + 60);
+        assert_eq!(events[0], Event::IncidentStarted(0));
+        assert!(!events.contains(&Event::StageLost));
+        assert_eq!(level.dirtiness_bounds(level.ongoing[0].object), (65, 175));
+    }
+}
@@ -0,0 +1,53 @@
+use macroquad::{
+    input::{
+        is_mouse_button_down, is_mouse_button_pressed, mouse_position, simulate_mouse_with_touch,
+        touches, MouseButton, TouchPhase,
+    },
+    math::Vec2,
+};
+
+use crate::letterbox::Letterbox;
+
+/// Something pointing at the screen this frame: the mouse cursor or a single finger.
+///
+/// Gameplay only ever looks at pointers so mouse and touch share the same rules.
+#[derive(Clone, Copy, Debug)]
+pub struct Pointer {
+    pub pos: Vec2,
+    /// went down this frame (a click or the start of a tap)
+    pub pressed: bool,
+    /// is being held down
+    pub down: bool,
+}
+
+/// Call once before the first frame. Touches are read as pointers of their own, so macroquad
+/// must not also turn them into mouse clicks or every tap would count twice.
+pub fn init() {
+    simulate_mouse_with_touch(false);
+}
+
+/// Mouse cursor first, followed by every active touch point, in logical game coordinates.
+pub fn pointers(letterbox: &Letterbox) -> Vec<Pointer> {
+    let mut pointers = vec![Pointer {
+        pos: letterbox.to_logical(mouse_position().into()),
+        pressed: is_mouse_button_pressed(MouseButton::Left),
+        down: is_mouse_button_down(MouseButton::Left),
+    }];
+    pointers.extend(touches().into_iter().map(|touch| Pointer {
+        pos: letterbox.to_logical(touch.position),
+        pressed: touch.phase == TouchPhase::Started,
+        down: matches!(
+            touch.phase,
+            TouchPhase::Started | TouchPhase::Stationary | TouchPhase::Moved
+        ),
+    }));
+    pointers
+}
+
+pub fn any_pressed(pointers: &[Pointer]) -> bool {
+    pointers.iter().any(|p| p.pressed)
+}
+
+pub fn any_down(pointers: &[Pointer]) -> bool {
+    pointers.iter().any(|p| p.down)
+}
@@ -0,0 +1,69 @@
+use macroquad::{
+    camera::{set_camera, set_default_camera, Camera2D},
+    color::{BLACK, WHITE},
+    math::{vec2, Vec2},
+    texture::{draw_texture_ex, render_target, DrawTextureParams, FilterMode, RenderTarget},
+    window::{clear_background, screen_height, screen_width},
+};
+
+/// Fits the fixed logical game area into whatever the window currently is.
+///
+/// The game is drawn in `size` logical units onto an offscreen texture, which is then scaled up
+/// or down to fill as much of the window as possible while keeping its aspect ratio, leaving
+/// black bars on the remaining sides. Drawing offscreen (rather than through a viewport) keeps
+/// things that start their own render pass, like particles, in the right place.
+pub struct Letterbox {
+    size: Vec2,
+    target: RenderTarget,
+    scale: f32,
+    offset: Vec2,
+}
+
+impl Letterbox {
+    pub fn new(size: Vec2) -> Self {
+        let target = render_target(size.x as u32, size.y as u32);
+        target.texture.set_filter(FilterMode::Nearest);
+        Letterbox {
+            size,
+            target,
+            scale: 1.0,
+            offset: vec2(0.0, 0.0),
+        }
+    }
+
+    /// Fits to the window as it is this frame; draw everything after this call in logical units.
+    pub fn begin(&mut self) {
+        let window = vec2(screen_width(), screen_height());
+        self.scale = f32::min(window.x / self.size.x, window.y / self.size.y);
+        self.offset = (window - self.size * self.scale) / 2.0;
+
+        set_camera(&Camera2D {
+            target: self.size / 2.0,
+            zoom: vec2(2.0 / self.size.x, -2.0 / self.size.y),
+            render_target: Some(self.target.clone()),
+            ..Default::default()
+        });
+    }
+
+    /// Puts this frame's drawing on the window.
+    pub fn present(&self) {
+        set_default_camera();
+        clear_background(BLACK);
+        draw_texture_ex(
+            &self.target.texture,
+            self.offset.x,
+            self.offset.y,
+            WHITE,
+            DrawTextureParams {
+                dest_size: Some(self.size * self.scale),
+                flip_y: true,
+                ..Default::default()
+            },
+        );
+    }
+
+    /// Maps a position in window coordinates (mouse, touch) to logical coordinates.
+    pub fn to_logical(&self, screen: Vec2) -> Vec2 {
+        (screen - self.offset) / self.scale
+    }
+}
@@ -0,0 +1,425 @@
+//! A stage being played: the dirty objects, their ants and everything the player has, and how
+//! all of that moves on from one tick to the next.
+
+use macroquad::math::{vec2, Rect, Vec2};
+
+use crate::{
+    assets::ResourceName,
+    effects::Effect,
+    events::{self, Event},
+    grid::Grid,
+    incidents::{self, Impact, Incident, Ongoing},
+    input::{self, Pointer},
+    rules::Ruleset,
+    sprite::{self, AntLook},
+    util::{LineSegment, Path},
+};
+
+pub const GAME_WIDTH: u16 = 960;
+pub const GAME_HEIGHT: u16 = 540;
+const STOE_SHIFT: f32 = 0.3;
+pub const MAX_DIRTINESS: u8 = 200;
+pub const WARN_DIRTINESS: u8 = 180;
+#[rustfmt::skip]
+pub const NOISE: [f32; 100] = [0.0, 0.37567067, 0.9067937, 0.47849727, 0.53902316, 0.68121976, 0.8017116, 0.3828842, 0.09980044, 0.28901517, 0.819964, 0.07882048, 0.9314874, 0.2782374, 0.8892265, 0.7379155, 0.8957271, 0.28707007, 0.38089857, 0.65332454, 0.012101332, 0.6167583, 0.821882, 0.05945961, 0.92279524, 0.03035006, 0.7336123, 0.98893404, 0.99925655, 0.35572338, 0.9292264, 0.88346875, 0.85185605, 0.68569475, 0.14773135, 0.6225942, 0.48433545, 0.1802073, 0.17406808, 0.26091358, 0.25314412, 0.3917573, 0.21147245, 0.88591653, 0.06278534, 0.45477942, 0.21266633, 0.92625904, 0.5458369, 0.9122172, 0.5397183, 0.035206992, 0.428736, 0.40691206, 0.754005, 0.49157023, 0.384951, 0.520259, 0.692683, 0.3089388, 0.65079826, 0.29621452, 0.8601855, 0.5781134, 0.63684237, 0.9962076, 0.3542669, 0.8180771, 0.7678995, 0.82436645, 0.72423524, 0.2671644, 0.56586105, 0.77570736, 0.11471661, 0.6794964, 0.8524261, 0.1201895, 0.21402203, 0.9767727, 0.5880526, 0.4113872, 0.8640513, 0.026697583, 0.12278987, 0.36087683, 0.86676, 0.082543656, 0.76316553, 0.6951772, 0.28111908, 0.70043737, 0.43776283, 0.086626664, 0.05120758, 0.5787454, 0.01473637, 0.8254751, 0.46910095, 0.42112306];
+
+#[derive(Clone)]
+pub struct DirtyObj {
+    pub dirtiness: u8,
+    pub trail: Trail,
+    pub amount: usize,
+    pub distance: Vec2,
+    pub chance_bidir: u8,
+}
+
+/// What the ants of an object walk along.
+#[derive(Clone)]
+pub enum Trail {
+    /// from a point on the first segment straight to the matching point on the second
+    Between(LineSegment, LineSegment),
+    /// along the path, spread out over its width
+    Along(Path),
+}
+
+impl Trail {
+    /// Whether `point` is on the area the ants walk over.
+    pub fn covers(&self, point: Vec2) -> bool {
+        match self {
+            Trail::Between(start, end) => start.lies_between(end, point),
+            Trail::Along(path) => path.contains(point),
+        }
+    }
+}
+
+/// What a single ant of an object walks along, see `DirtyObj::lanes`.
+enum Lane<'a> {
+    Between(Vec2, Vec2),
+    Along(&'a Path),
+}
+
+/// One of the ants of `LevelState::objects`, see `LevelState::ants`.
+#[derive(Clone, Copy)]
+pub struct Ant {
+    pub object: usize,
+    /// which of the object's ants
+    pub idx: usize,
+    pub heading: Vec2,
+}
+
+/// An ant that was cleaned up, shown in its death frame for `sprite::CORPSE_TICKS`.
+#[derive(Clone)]
+pub struct Corpse {
+    pub pos: Vec2,
+    pub heading: Vec2,
+    pub look: AntLook,
+    /// when it died
+    pub tick: usize,
+}
+
+/// Feedback on a pointer during a tick, which should be heard or seen. What the tick led to is
+/// an `Event` instead.
+#[derive(PartialEq)]
+pub enum Cue {
+    Sound(ResourceName),
+    Effect(Effect, Vec2),
+}
+
+#[derive(Clone)]
+pub struct LevelState {
+    /// ticks simulated in this stage, stands still while paused
+    pub tick: usize,
+    pub objects: Vec<DirtyObj>,
+    /// how the stage plays, picked by the level file's `difficulty`
+    pub rules: &'static dyn Ruleset,
+    pub money: f32,
+    pub money_goal: f32,
+    pub repellants: u16,
+    pub repellant_name: String,
+    pub repellant_price: f32,
+    /// particle effects shown in this stage
+    pub effects: Vec<Effect>,
+    /// how big an ant is drawn, the scenes are not all to the same scale
+    pub ant_size: f32,
+    pub corpses: Vec<Corpse>,
+    /// every ant on screen, as of the last tick
+    pub ants: Grid<Ant>,
+    /// what can happen by chance, from the level file's `incident` lines
+    pub incidents: Vec<Incident>,
+    pub ongoing: Vec<Ongoing>,
+    /// where the chances of incidents are drawn from, set to the game's seed as the stage starts
+    pub rng: u64,
+}
+
+impl LevelState {
+    /// Takes the layout and rules of `def`, a freshly read level file, while keeping the progress
+    /// made so far.
+    pub fn reload(&mut self, def: &LevelState) {
+        let progress = (self.tick, self.money, self.repellants, self.rng);
+        let dirtiness: Vec<u8> = self.objects.iter().map(|object| object.dirtiness).collect();
+        *self = def.clone();
+        (self.tick, self.money, self.repellants, self.rng) = progress;
+        for (object, dirtiness) in self.objects.iter_mut().zip(dirtiness) {
+            object.dirtiness = dirtiness;
+        }
+        self.index_ants();
+    }
+
+    /// The dirtiness `object` is to be kept within right now, the rules' bounds narrowed by any
+    /// incident going on with it as far as it eased in.
+    pub fn dirtiness_bounds(&self, object: usize) -> (u8, u8) {
+        let (mut min, mut max) = self.rules.dirtiness_bounds();
+        for ongoing in self
+            .ongoing
+            .iter()
+            .filter(|ongoing| ongoing.object == object)
+        {
+            let incident = &self.incidents[ongoing.incident];
+            if let Impact::TightenOne(by) = incident.impact {
+                let by = (by as f32 * ongoing.eased_in(incident)).round() as u8;
+                min = min.saturating_add(by);
+                max = max.saturating_sub(by);
+            }
+        }
+        (min, max)
+    }
+
+    /// Ticks left until the shift is over, for the rules that have one.
+    pub fn shift_left(&self) -> Option<usize> {
+        let length = self.rules.shift_length()?;
+        Some(length.saturating_sub(self.tick))
+    }
+
+    /// Puts every ant that shows up this tick into `ants`.
+    pub fn index_ants(&mut self) {
+        self.ants.clear();
+        let randomize_end = self.rules.scatter_ants();
+        for (object_id, object) in self.objects.iter().enumerate() {
+            for (idx, lane) in object.lanes().enumerate() {
+                if !ant_visible(object.dirtiness, idx) {
+                    continue;
+                }
+                let (pos, heading) = lerp_ant(self.tick, object, lane, idx, randomize_end);
+                self.ants.insert(
+                    pos,
+                    Ant {
+                        object: object_id,
+                        idx,
+                        heading,
+                    },
+                );
+            }
+        }
+        self.ants.build();
+    }
+}
+
+/// Cell size of `LevelState::ants`, about as far as a pointer reaches.
+pub const ANT_CELL: f32 = 24.0;
+
+pub fn ant_grid() -> Grid<Ant> {
+    Grid::new(game_rect(), ANT_CELL)
+}
+
+pub fn game_rect() -> Rect {
+    Rect::new(0.0, 0.0, GAME_WIDTH as f32, GAME_HEIGHT as f32)
+}
+
+/// Advances the stage by one tick: dirtiness, cleaning, income and purchases.
+///
+/// What came out of it is pushed to `events`, ending with `StageWon` or `StageLost` once the
+/// stage is over. Feedback on the pointers is pushed to `cues`.
+pub fn update_level(
+    level_state: &mut LevelState,
+    pointers: &[Pointer],
+    events: &mut Vec<Event>,
+    cues: &mut Vec<Cue>,
+) {
+    level_state.tick += 1;
+    let tick = level_state.tick;
+    let rules = level_state.rules;
+    let money_before = level_state.money;
+
+    level_state
+        .corpses
+        .retain(|corpse| tick - corpse.tick < sprite::CORPSE_TICKS);
+
+    let holding = input::any_down(pointers);
+
+    // ants are hit where they were drawn last frame
+    let reach = rules.reach(level_state.repellants);
+    for (object_id, object) in level_state.objects.iter_mut().enumerate() {
+        let was_warned = object.dirtiness > WARN_DIRTINESS;
+        let was_complete_dirty = object.dirtiness > MAX_DIRTINESS;
+
+        rules.on_tick(object, tick, level_state.repellants);
+
+        // kill ants or make money, once for every pointer held on the object
+        if holding {
+            // picked out first, the rules get the whole object to change
+            let on_object: Vec<&Pointer> = pointers
+                .iter()
+                .filter(|p| {
+                    p.down
+                        && (object.trail.covers(p.pos)
+                            || level_state
+                                .ants
+                                .within(p.pos, reach)
+                                .any(|(_, ant)| ant.object == object_id))
+                })
+                .collect();
+            for pointer in on_object {
+                // a sound on every tick of holding would just be noise
+                if pointer.pressed || tick.is_multiple_of(12) {
+                    cue(cues, Cue::Sound(rules.click_sound()));
+                }
+                if level_state.effects.contains(&Effect::Splat)
+                    && (pointer.pressed || tick.is_multiple_of(6))
+                {
+                    // on the ant that was aimed at, if there was one
+                    let splat = match level_state.ants.nearest(pointer.pos, reach) {
+                        Some((pos, _)) => pos,
+                        None => pointer.pos,
+                    };
+                    cue(cues, Cue::Effect(Effect::Splat, splat));
+                }
+                let before = object.dirtiness;
+                rules.on_click(object);
+                // the ants under the pointer that went away were cleaned up, leave them lying
+                // around for a bit. the rest ran off
+                for (pos, ant) in level_state.ants.within(pointer.pos, reach) {
+                    if ant.object == object_id
+                        && ant_visible(before, ant.idx)
+                        && !ant_visible(object.dirtiness, ant.idx)
+                    {
+                        level_state.corpses.push(Corpse {
+                            pos,
+                            heading: ant.heading,
+                            look: rules.ant_sprite(object, ant.idx),
+                            tick,
+                        });
+                    }
+                }
+                // make money when mouse button down
+                level_state.money += rules.income(true, level_state.repellants);
+            }
+        } else {
+            // make money when mouse button not down
+            level_state.money += rules.income(false, level_state.repellants);
+        }
+
+        if !was_warned && object.dirtiness > WARN_DIRTINESS {
+            events.push(Event::ObjectWarned(object_id));
+        }
+        if !was_complete_dirty && object.dirtiness > MAX_DIRTINESS {
+            events.push(Event::ObjectCritical(object_id));
+        }
+    }
+    level_state.index_ants();
+
+    // buying supplements
+    if pointers
+        .iter()
+        .any(|p| p.pressed && rep_btn_rect().contains(p.pos))
+        && level_state.money > level_state.repellant_price
+    {
+        level_state.repellants += 1;
+        level_state.money -= level_state.repellant_price;
+        events.push(Event::Purchased(level_state.repellant_price));
+    }
+
+    for percent in events::MONEY_MILESTONES {
+        let milestone = level_state.money_goal * percent as f32 / 100.0;
+        if money_before < milestone && level_state.money >= milestone {
+            events.push(Event::MoneyMilestone(percent));
+        }
+    }
+
+    incidents::update(level_state, events);
+
+    // change in state
+    let shift_over = rules.shift_length().is_some_and(|length| tick > length);
+    if rules.is_won(level_state) {
+        events.push(Event::StageWon);
+    } else if shift_over || rules.is_lost(level_state) {
+        if shift_over {
+            events.push(Event::ShiftEnded);
+        }
+        events.push(Event::StageLost);
+    }
+}
+
+fn cue(cues: &mut Vec<Cue>, cue: Cue) {
+    if !cues.contains(&cue) {
+        cues.push(cue);
+    }
+}
+
+pub fn rep_btn_rect() -> Rect {
+    let (width, height) = (170.0, 30.0);
+    Rect::new(GAME_WIDTH as f32 - width - 10.0, 10.0, width, height)
+}
+
+impl DirtyObj {
+    /// Where each of the object's ants walks.
+    fn lanes(&self) -> Box<dyn Iterator<Item = Lane<'_>> + '_> {
+        match &self.trail {
+            Trail::Between(start, end) => Box::new(
+                start
+                    .points_on(self.amount)
+                    .zip(end.points_on(self.amount))
+                    .map(|(start, end)| Lane::Between(start, end)),
+            ),
+            Trail::Along(path) => Box::new((0..self.amount).map(move |_| Lane::Along(path))),
+        }
+    }
+
+    pub fn center(&self) -> Vec2 {
+        match &self.trail {
+            Trail::Between(start, end) => (start.start + start.end + end.start + end.end) / 4.0,
+            Trail::Along(path) => path.at(path.length() / 2.0).0,
+        }
+    }
+
+    pub fn new(dirtiness: u8, trail: Trail, amount: usize, chance_bidir: u8) -> Self {
+        let distance = match &trail {
+            Trail::Between(start, end) => {
+                ((end.start + end.end) / vec2(2.0, 2.0))
+                    - ((start.start + start.end) / vec2(2.0, 2.0))
+            }
+            Trail::Along(path) => path.end() - path.start(),
+        };
+        DirtyObj {
+            dirtiness,
+            trail,
+            amount,
+            distance,
+            chance_bidir,
+        }
+    }
+}
+
+fn lerp_ant(
+    tick: usize,
+    object: &DirtyObj,
+    lane: Lane,
+    seed: usize,
+    randomize_end: bool,
+) -> (Vec2, Vec2) {
+    let reverse = NOISE[(seed + 1) % 100] * 255.0 < object.chance_bidir as f32;
+    let (mut start, mut end) = match lane {
+        Lane::Between(start, end) => (start, end),
+        Lane::Along(path) => return walk_path(tick, object, path, seed, reverse),
+    };
+    if reverse {
+        std::mem::swap(&mut start, &mut end);
+    }
+    if randomize_end {
+        end += vec2(
+            (NOISE[(seed + 20) % 100] * 2.0 - 1.0) * STOE_SHIFT * object.distance.y,
+            (NOISE[(seed + 30) % 100] * 2.0 - 1.0) * STOE_SHIFT * object.distance.x,
+        );
+    }
+
+    let tick_x = tick + (NOISE[seed % 100] * 20.0 * object.amount as f32) as usize;
+    let tick_y = tick + (NOISE[seed % 100] * 20.0 * object.amount as f32) as usize;
+
+    let dist_x = end.x - start.x;
+    let dist_y = end.y - start.y;
+
+    let x = start.x
+        + (((tick_x as f32 * 0.002) * (end.x - start.x))
+            % if dist_x == 0.0 { 1.0 } else { dist_x });
+    let y = start.y
+        + (((tick_y as f32 * 0.002) * (end.y - start.y))
+            % if dist_y == 0.0 { 1.0 } else { dist_y });
+    (vec2(x, y), end - start)
+}
+
+/// Like `lerp_ant`, for an ant on a path: it takes as long to walk as a `Trail::Between` of any
+/// length, and keeps to its own side of the path.
+fn walk_path(
+    tick: usize,
+    object: &DirtyObj,
+    path: &Path,
+    seed: usize,
+    reverse: bool,
+) -> (Vec2, Vec2) {
+    let tick = tick + (NOISE[seed % 100] * 20.0 * object.amount as f32) as usize;
+    let length = path.length();
+    let mut distance = (tick as f32 * 0.002 * length) % length.max(f32::EPSILON);
+    if reverse {
+        distance = length - distance;
+    }
+    let (pos, mut heading) = path.at(distance);
+    let side = (NOISE[(seed + 20) % 100] * 2.0 - 1.0) * path.width / 2.0;
+    let pos = pos + heading.perp() * side;
+    if reverse {
+        heading = -heading;
+    }
+    (pos, heading)
+}
+
+/// Whether ant `seed` of an object shows up at this much dirtiness.
+pub fn ant_visible(dirtiness: u8, seed: usize) -> bool {
+    f32::powi(NOISE[seed % 100], 2) * dirtiness as f32 >= 63.0
+}
@@ -0,0 +1,23 @@
+//! Everything about the game that is not its screens: assets, levels, the rules they are played
+//! by and their replays, shared by the game, the balancing simulator and the benchmarks.
+
+pub mod assets;
+pub mod atlas;
+pub mod batch;
+pub mod data;
+pub mod effects;
+pub mod events;
+pub mod grid;
+pub mod incidents;
+pub mod input;
+pub mod letterbox;
+pub mod level;
+pub mod replay;
+pub mod rules;
+pub mod sprite;
+pub mod stages;
+pub mod story;
+#[cfg(test)]
+mod testing;
+pub mod tutorial;
+pub mod util;
@@ -1,752 +1,1077 @@
-use std::{
-    collections::{HashMap, VecDeque},
-    fmt,
-    slice::Iter,
-};
+use std::collections::HashMap;
 
 use macroquad::{
     color::*,
-    input::{is_mouse_button_down, is_mouse_button_pressed, mouse_position, MouseButton},
-    math::{vec2, Vec2},
+    input::{is_key_pressed, KeyCode},
+    math::{vec2, Rect, Vec2},
+    miniquad::{self, window::order_quit},
     rand::gen_range,
-    shapes::draw_rectangle,
-    text::draw_text,
-    texture::{draw_texture, load_texture, Texture2D},
-    window::{clear_background, next_frame, Conf},
+    shapes::{draw_circle_lines, draw_line, draw_rectangle, draw_rectangle_lines},
+    text::{draw_text, measure_text},
+    texture::draw_texture,
+    time::{get_fps, get_frame_time},
+    window::{clear_background, next_frame, set_fullscreen, Conf},
 };
 
-mod util;
-pub use util::*;
+mod audio;
+mod cli;
+mod hot_reload;
+mod menu;
+mod settings;
+mod transition;
+use audio::Jukebox;
+use cli::Options;
+use clicker1::{
+    assets::{self, AssetError, AssetKind, Loader, ResourceName, Resources},
+    batch::SpriteBatch,
+    data,
+    effects::{Effect, Effects},
+    events::Event,
+    input::{self, Pointer},
+    letterbox::Letterbox,
+    level::*,
+    replay::{Outcome, Recording, Replay},
+    sprite::{self, Pose},
+    stages::Stage,
+    story::StoryIter,
+    tutorial::{self, Focus, Tutorial, Until},
+};
+use hot_reload::Watcher;
+use menu::{PauseAction, PauseMenu};
+use settings::Settings;
+use transition::{Kind, Transition};
 
-const GAME_WIDTH: u16 = 960;
-const GAME_HEIGHT: u16 = 540;
-const STOE_SHIFT: f32 = 0.3;
-const MAX_DIRTINESS: u8 = 200;
-const WARN_DIRTINESS: u8 = 180;
-#[rustfmt::skip]
-const NOISE: [f32; 100] = [0.0, 0.37567067, 0.9067937, 0.47849727, 0.53902316, 0.68121976, 0.8017116, 0.3828842, 0.09980044, 0.28901517, 0.819964, 0.07882048, 0.9314874, 0.2782374, 0.8892265, 0.7379155, 0.8957271, 0.28707007, 0.38089857, 0.65332454, 0.012101332, 0.6167583, 0.821882, 0.05945961, 0.92279524, 0.03035006, 0.7336123, 0.98893404, 0.99925655, 0.35572338, 0.9292264, 0.88346875, 0.85185605, 0.68569475, 0.14773135, 0.6225942, 0.48433545, 0.1802073, 0.17406808, 0.26091358, 0.25314412, 0.3917573, 0.21147245, 0.88591653, 0.06278534, 0.45477942, 0.21266633, 0.92625904, 0.5458369, 0.9122172, 0.5397183, 0.035206992, 0.428736, 0.40691206, 0.754005, 0.49157023, 0.384951, 0.520259, 0.692683, 0.3089388, 0.65079826, 0.29621452, 0.8601855, 0.5781134, 0.63684237, 0.9962076, 0.3542669, 0.8180771, 0.7678995, 0.82436645, 0.72423524, 0.2671644, 0.56586105, 0.77570736, 0.11471661, 0.6794964, 0.8524261, 0.1201895, 0.21402203, 0.9767727, 0.5880526, 0.4113872, 0.8640513, 0.026697583, 0.12278987, 0.36087683, 0.86676, 0.082543656, 0.76316553, 0.6951772, 0.28111908, 0.70043737, 0.43776283, 0.086626664, 0.05120758, 0.5787454, 0.01473637, 0.8254751, 0.46910095, 0.42112306];
+const TITLE: &str = "My Life with Ants in 2027";
 
 fn conf() -> Conf {
     Conf {
-        window_title: "My Life with Ants in 2027".into(),
+        window_title: TITLE.into(),
         window_width: GAME_WIDTH as i32,
         window_height: GAME_HEIGHT as i32,
         fullscreen: false,
-        window_resizable: false,
+        window_resizable: true,
         ..Default::default()
     }
 }
 
-#[derive(Clone, Hash, PartialEq, Eq)]
-enum ResourceName {
-    ImageA1,
-    ImageB1,
-    Ant,
-    AntSV,
-    AntSVCrit,
-    AntSH,
-    AntSHCrit,
-    AntCrit,
-    Story1,
-    Story2,
-    Story3,
-}
-type Resources = HashMap<ResourceName, Texture2D>;
-
 struct GameState {
-    screen: Screen,
+    /// bottom first, never empty. only the top screen and whatever it passes through to run, the
+    /// rest are just drawn
+    screens: Vec<Screen>,
+    /// set while going over to the next screen, which takes no input until it is done
+    transition: Option<Transition<Screen>>,
     levels: HashMap<Stage, LevelState>,
+    stories: HashMap<ResourceName, StoryIter>,
+    settings: Settings,
+    /// what `macroquad::rand` is seeded with whenever a stage starts
+    seed: u64,
+    /// the stage being played, saved once it is over
+    recording: Option<Recording>,
+    /// set when playing back a replay file instead of taking input
+    replay: Option<Replay>,
+    /// a level from `--level-file`, played instead of the stage's own
+    level_file: Option<(Stage, LevelState)>,
+    skip_story: bool,
+    /// draw what the simulation sees over the stage
+    debug: bool,
 }
 
+// there are only ever a few screens, boxing the stage would not save anything
+#[allow(clippy::large_enum_variant)]
 enum Screen {
+    Loading(Loader),
     MainMenu,
     Stage(Stage, LevelState),
     Dialog(Dialog),
+    /// over a stage, which stands still underneath
+    Pause(PauseMenu, Stage),
+    /// a line of text over whatever is below, going away by itself after `frames_left`
+    Banner {
+        text: String,
+        frames_left: u32,
+    },
+    /// walks through a stage while it is played underneath
+    Tutorial(Tutorial, Stage),
 }
 
-#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
-enum Stage {
-    A1,
-    B1,
-}
-impl fmt::Display for Stage {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.write_str(match self {
-            Stage::A1 => "A1",
-            Stage::B1 => "B2",
-        })
-    }
-}
-
-struct StoryIter {
-    pages: VecDeque<(ResourceName, Vec<String>)>,
-}
-
-impl StoryIter {
-    fn peek(&self) -> Option<(ResourceName, Vec<String>)> {
-        match self.pages.len() {
-            0 => None,
-            _ => Some(self.pages.get(0).unwrap().clone()),
+impl Screen {
+    /// Whether the screen below keeps running and gets input while this one is over it, instead
+    /// of only being drawn.
+    fn passes_through(&self) -> bool {
+        match self {
+            Screen::Banner { .. } => true,
+            Screen::Tutorial(tutorial, _) => tutorial.step().is_none_or(|step| !step.stands_still),
+            Screen::Loading(_)
+            | Screen::MainMenu
+            | Screen::Stage(..)
+            | Screen::Dialog(_)
+            | Screen::Pause(..) => false,
         }
     }
 }
 
-impl Iterator for StoryIter {
-    type Item = (ResourceName, Vec<String>);
-
-    fn next(&mut self) -> Option<Self::Item> {
-        self.pages.pop_front()
-    }
+/// What a screen wants done with the stack once every screen had its turn this frame.
+enum Change {
+    /// goes over to a new screen through a transition, dropping every screen there is now
+    Replace(Screen),
+    /// puts an overlay on top
+    Push(Screen),
+    /// takes away the screen asking for it
+    Close,
 }
 
+/// How long the name of a stage stays up when it starts.
+const BANNER_FRAMES: u32 = 150;
+
 enum Dialog {
     Lost(Stage),
-    Won(Stage, Stage),
+    Won(Stage),
     Story(StoryIter, Stage),
     Thanks,
 }
 
-#[derive(Clone)]
-struct DirtyObj {
-    dirtiness: u8,
-    start: LineSegment,
-    end: LineSegment,
-    amount: usize,
-    distance: Vec2,
-    chance_bidir: u8,
-}
-
-#[derive(Clone, PartialEq, Eq)]
-enum Difficulty {
-    Easy,
-    Medium,
-}
-
-#[derive(Clone)]
-struct LevelState {
-    objects: Vec<DirtyObj>,
-    difficulty: Difficulty,
-    scene: ResourceName,
-    money: f32,
-    money_goal: f32,
-    repellants: u16,
-    repellant_name: String,
-    repellant_price: f32,
+/// The particles that go with `event`, if the stage shows them.
+fn event_effect(level_state: &LevelState, event: &Event) -> Option<(Effect, Vec2)> {
+    let (effect, pos) = match event {
+        Event::ObjectCritical(object) => (Effect::Dust, level_state.objects[*object].center()),
+        Event::Purchased(_) => (Effect::Coins, rep_btn_rect().center()),
+        _ => return None,
+    };
+    level_state
+        .effects
+        .contains(&effect)
+        .then_some((effect, pos))
 }
 
-fn manage_level(
-    level_state: &mut LevelState,
-    resources: &Resources,
-    tick: usize,
-    duration: &mut Option<usize>,
+/// Where a stage leads once it is over.
+fn stage_over(
+    stage: Stage,
+    levels: &HashMap<Stage, LevelState>,
+    stories: &HashMap<ResourceName, StoryIter>,
+    skip_story: bool,
+    events: &[Event],
 ) -> Option<Screen> {
-    static MEDIUM_DURATION: usize = 4 * 60 * 60;
-    use ResourceName::*;
-    //draw scene
-    draw_texture(resources.get(&level_state.scene).unwrap(), 0.0, 0.0, WHITE);
+    events.iter().find_map(|event| match event {
+        Event::StageWon if skip_story => Some(after_win(levels, stories, skip_story, stage)),
+        Event::StageWon => Some(Screen::Dialog(Dialog::Won(stage))),
+        Event::StageLost => Some(Screen::Dialog(Dialog::Lost(stage))),
+        _ => None,
+    })
+}
 
-    match level_state.difficulty {
-        Difficulty::Easy => {}
-        Difficulty::Medium => match duration {
-            Some(d) => {
-                *d += 1;
-            }
-            None => {
-                *duration = Some(0);
-            }
-        },
+/// The stage after `stage`, or the end of the game after the last one.
+fn after_win(
+    levels: &HashMap<Stage, LevelState>,
+    stories: &HashMap<ResourceName, StoryIter>,
+    skip_story: bool,
+    stage: Stage,
+) -> Screen {
+    match stage.info().next {
+        Some(next) => enter_stage(levels, stories, skip_story, next),
+        None => Screen::Dialog(Dialog::Thanks),
     }
+}
 
-    let mut objects_complete_dirty = 0;
-    let mut num_objects = 0;
-    let mouse_pos = mouse_position();
-
-    //draw ants
-    for (object_id, object) in &mut level_state.objects.iter_mut().enumerate() {
-        // let dirtiness = f32::floor(object.dirtiness as f32 / 20.0) * 20.0;
-        // let speed = (f32::powi(dirtiness, 3)) * 0.007;
-
-        for (idx, (start, end)) in object
-            .start
-            .points_on(object.amount)
-            .zip(object.end.points_on(object.amount))
-            .enumerate()
+/// How going from `from` to `to` looks.
+fn transition_kind(from: &Screen, to: &Screen, settings: &Settings) -> Kind {
+    match (from, to) {
+        _ if settings.reduce_motion => Kind::Fade,
+        (Screen::Stage(stage, _), Screen::Dialog(Dialog::Lost(_)))
+            if stage.info().swarmed_on_loss =>
         {
-            let (ant, (off_x, off_y)) = match level_state.difficulty {
-                Difficulty::Easy => match object.dirtiness > WARN_DIRTINESS {
-                    false => (resources.get(&Ant).unwrap(), (4.0, 4.0)),
-                    true => (
-                        resources
-                            .get(if NOISE[idx % 100] > 0.9 {
-                                &AntCrit
-                            } else {
-                                &Ant
-                            })
-                            .unwrap(),
-                        (4.0, 4.0),
-                    ),
-                },
-                Difficulty::Medium => match object_id {
-                    1 => match object.dirtiness > WARN_DIRTINESS {
-                        false => (resources.get(&AntSH).unwrap(), (1.0, 0.0)),
-                        true => (
-                            resources
-                                .get(if NOISE[idx % 100] > 0.7 {
-                                    &AntSHCrit
-                                } else {
-                                    &AntSH
-                                })
-                                .unwrap(),
-                            (1.0, 0.0),
-                        ),
-                    },
-                    _ => match object.dirtiness > WARN_DIRTINESS {
-                        false => (resources.get(&AntSV).unwrap(), (0.0, 1.0)),
-                        true => (
-                            resources
-                                .get(if NOISE[idx % 100] > 0.7 {
-                                    &AntSVCrit
-                                } else {
-                                    &AntSV
-                                })
-                                .unwrap(),
-                            (0.0, 1.0),
-                        ),
-                    },
-                },
-            };
-
-            let (x, y) = match lerp_ant(
-                tick,
-                &object,
-                start,
-                end,
-                idx,
-                match level_state.difficulty {
-                    Difficulty::Easy => true,
-                    Difficulty::Medium => false,
-                },
-            ) {
-                Some(p) => p,
-                None => {
-                    continue;
-                }
-            };
-
-            draw_texture(
-                ant,
-                (x - off_x) + (gen_range(-1.0, 1.0)) * (0.01 * object.distance.y),
-                (y - off_y) + (gen_range(-1.0, 1.0)) * (0.01 * object.distance.x),
-                WHITE,
-            );
+            Kind::Death
         }
+        (Screen::Dialog(Dialog::Story(..)), Screen::Stage(..)) => Kind::Wipe,
+        _ => Kind::Fade,
+    }
+}
 
-        let update_dirtiness: bool = match level_state.difficulty {
-            Difficulty::Easy => {
-                let rep_effect = (3.0 * (level_state.repellants as f32 / 10.0)) as usize;
-                tick % (7 + rep_effect) == 0
-            }
-            Difficulty::Medium => {
-                // let rep_effect = (2.0 + (5.0 / level_state.repellants as f32)) as usize;
-                tick % 7 == 0
-            }
-        };
+/// The story leading into `stage`, or the stage itself if stories are skipped.
+fn enter_stage(
+    levels: &HashMap<Stage, LevelState>,
+    stories: &HashMap<ResourceName, StoryIter>,
+    skip_story: bool,
+    stage: Stage,
+) -> Screen {
+    match skip_story {
+        true => Screen::Stage(stage, levels[&stage].clone()),
+        false => Screen::Dialog(Dialog::Story(stories[&stage.info().story].clone(), stage)),
+    }
+}
 
-        if update_dirtiness {
-            object.dirtiness = match level_state.difficulty {
-                Difficulty::Easy => object.dirtiness.checked_add(1).unwrap_or(object.dirtiness),
-                Difficulty::Medium => object.dirtiness.checked_sub(1).unwrap_or(object.dirtiness),
-            };
-        }
+/// Where the drones (B1's repellant) are flying this tick, circling over the street.
+fn drone_positions(level_state: &LevelState) -> Vec<Vec2> {
+    const MAX_DRONES_SHOWN: u16 = 8;
+    let drones = level_state.rules.drones(level_state.repellants);
+    (0..drones.min(MAX_DRONES_SHOWN))
+        .map(|idx| {
+            let angle = level_state.tick as f32 * 0.01
+                + idx as f32 * std::f32::consts::TAU / MAX_DRONES_SHOWN as f32;
+            vec2(500.0 + 170.0 * angle.cos(), 220.0 + 70.0 * angle.sin())
+        })
+        .collect()
+}
 
-        // kill ants or make money
-        if is_mouse_button_down(MouseButton::Left) {
-            if object.start.lies_between(&object.end, mouse_pos.into()) {
-                // println!(
-                //     "Clicked in object {object_id}, dirtiness now: {}",
-                //     object.dirtiness
-                // );
-                match level_state.difficulty {
-                    Difficulty::Easy => {
-                        object.dirtiness =
-                            object.dirtiness.checked_sub(1).unwrap_or(object.dirtiness);
-                    }
-                    Difficulty::Medium => {
-                        object.dirtiness =
-                            object.dirtiness.checked_add(1).unwrap_or(object.dirtiness);
-                    }
-                }
-                // make money when mouse button down
-                match level_state.difficulty {
-                    Difficulty::Easy => {}
-                    Difficulty::Medium => {
-                        let rep_effect = 2.0 * (level_state.repellants as f32).ln();
-                        level_state.money += 0.5 + rep_effect;
-                    }
+/// What the simulation sees, drawn over the stage for `--debug`: the area of every trail, how far
+/// the pointers reach and some numbers.
+fn draw_debug(level_state: &LevelState, pointers: &[Pointer]) {
+    const TRAIL_STEPS: usize = 24;
+    for (object_id, object) in level_state.objects.iter().enumerate() {
+        match &object.trail {
+            Trail::Between(start, end) => {
+                for segment in [start, end] {
+                    let (a, b) = (segment.start, segment.end);
+                    draw_line(a.x, a.y, b.x, b.y, 1.0, YELLOW);
                 }
             }
-        } else {
-            // make money when mouse button not down
-            match level_state.difficulty {
-                Difficulty::Easy => {
-                    level_state.money += 0.1;
+            Trail::Along(path) => {
+                let points: Vec<Vec2> = (0..=TRAIL_STEPS)
+                    .map(|step| path.at(path.length() * step as f32 / TRAIL_STEPS as f32).0)
+                    .collect();
+                for pair in points.windows(2) {
+                    draw_line(
+                        pair[0].x, pair[0].y, pair[1].x, pair[1].y, path.width, YELLOW,
+                    );
                 }
-                Difficulty::Medium => {}
             }
-            // level_state.money += match level_state.difficulty {
-            //     Difficulty::Easy => 0.1,
-            //     Difficulty::Medium => 0.1,
-            //     Difficulty::Hard => todo!(),
-            // };
         }
+        let center = object.center();
+        draw_text(
+            &format!("#{object_id} {}", object.dirtiness),
+            center.x,
+            center.y,
+            16.0,
+            YELLOW,
+        );
+    }
 
-        num_objects += 1;
-        match level_state.difficulty {
-            Difficulty::Easy => {
-                if object.dirtiness > MAX_DIRTINESS {
-                    objects_complete_dirty += 1;
-                }
+    let reach = level_state.rules.reach(level_state.repellants);
+    for pointer in pointers {
+        let color = match pointer.down {
+            true => RED,
+            false => WHITE,
+        };
+        draw_circle_lines(pointer.pos.x, pointer.pos.y, reach, 1.0, color);
+    }
+
+    draw_text(
+        &format!(
+            "{} fps, tick {}, {} ants, {} corpses",
+            get_fps(),
+            level_state.tick,
+            level_state.ants.len(),
+            level_state.corpses.len()
+        ),
+        10.0,
+        GAME_HEIGHT as f32 - 10.0,
+        16.0,
+        YELLOW,
+    );
+}
+
+// top-center, where none of the scenes have anything to click on
+fn pause_btn_rect() -> Rect {
+    Rect::new(GAME_WIDTH as f32 / 2.0 - 15.0, 10.0, 30.0, 30.0)
+}
+
+// bottom-right, out of the way of the scenes and the buy button
+fn skip_btn_rect() -> Rect {
+    Rect::new(
+        GAME_WIDTH as f32 - 190.0,
+        GAME_HEIGHT as f32 - 50.0,
+        180.0,
+        40.0,
+    )
+}
+
+/// What `step` points at, and what it says at the bottom of the screen.
+fn draw_tutorial_step(step: &tutorial::Step, level_state: &LevelState, settings: &Settings) {
+    const METER_HEIGHT: f32 = 100.0;
+    match step.focus {
+        // a level file that does not go with the steps any more, there is nothing to point at
+        focus if !focus.is_shown(level_state) => {}
+        Focus::Nothing => {}
+        Focus::Object(object) => {
+            let center = level_state.objects[object].center();
+            draw_circle_lines(center.x, center.y, 60.0, 3.0, YELLOW);
+            // full at the most dirt there can be, with lines where the bounds are
+            let (x, y) = (center.x + 70.0, center.y - METER_HEIGHT / 2.0);
+            let height = |dirtiness: u8| METER_HEIGHT * dirtiness as f32 / u8::MAX as f32;
+            let dirtiness = height(level_state.objects[object].dirtiness);
+            draw_rectangle(x, y, 12.0, METER_HEIGHT, BLACK);
+            draw_rectangle(x, y + METER_HEIGHT - dirtiness, 12.0, dirtiness, BROWN);
+            let (min, max) = level_state.dirtiness_bounds(object);
+            for bound in [min, max].into_iter().filter(|bound| *bound > 0) {
+                let bound_y = y + METER_HEIGHT - height(bound);
+                draw_line(x - 3.0, bound_y, x + 15.0, bound_y, 2.0, RED);
             }
-            Difficulty::Medium => {
-                if object.dirtiness > MAX_DIRTINESS || object.dirtiness < 40 {
-                    objects_complete_dirty += 1;
-                }
+            draw_rectangle_lines(x, y, 12.0, METER_HEIGHT, 2.0, WHITE);
+        }
+        // where draw_level puts it
+        Focus::ShiftTimer => draw_rectangle_lines(4.0, 42.0, 240.0, 26.0, 3.0, YELLOW),
+        Focus::Drones => {
+            for drone in drone_positions(level_state) {
+                draw_circle_lines(drone.x, drone.y, 30.0, 3.0, YELLOW);
             }
         }
+        Focus::RepellantButton => {
+            let rect = rep_btn_rect();
+            draw_rectangle_lines(
+                rect.x - 4.0,
+                rect.y - 4.0,
+                rect.w + 8.0,
+                rect.h + 8.0,
+                3.0,
+                YELLOW,
+            );
+        }
+    }
+    let hint = (step.until == Until::Tapped).then_some("Tap to go on.");
+    let lines: Vec<&str> = step.lines.iter().copied().chain(hint).collect();
+    let top = GAME_HEIGHT as f32 - 20.0 - lines.len() as f32 * 26.0;
+    for (idx, line) in lines.iter().enumerate() {
+        draw_hud_text(line, 20.0, top + idx as f32 * 26.0, 24.0, settings);
+    }
+}
+
+fn draw_hud_text(text: &str, x: f32, y: f32, font_size: f32, settings: &Settings) {
+    if settings.high_contrast {
+        let size = measure_text(text, None, font_size as u16, 1.0);
+        draw_rectangle(
+            x - 4.0,
+            y - size.offset_y - 4.0,
+            size.width + 8.0,
+            size.height + 8.0,
+            BLACK,
+        );
+    }
+    draw_text(text, x, y, font_size, WHITE);
+}
+
+fn draw_level(
+    stage: Stage,
+    level_state: &LevelState,
+    resources: &Resources,
+    settings: &Settings,
+    batch: &mut SpriteBatch,
+) {
+    use ResourceName::*;
+    let tick = level_state.tick;
+    //draw scene
+    draw_texture(resources.get(&stage.info().scene).unwrap(), 0.0, 0.0, WHITE);
+
+    //draw ants
+    // every ant, corpse and drone goes out in one go
+    batch.begin(&resources.atlas.texture);
+    let ant_sheet = resources.sprite(&AntSheet).unwrap();
+    let jitter = match settings.reduce_motion {
+        true => 0.0,
+        false => 0.01,
+    };
+    // anything off screen is skipped
+    for (pos, ant) in level_state.ants.inside(game_rect()) {
+        let object = &level_state.objects[ant.object];
+        let jitter = vec2(
+            gen_range(-1.0, 1.0) * jitter * object.distance.y,
+            gen_range(-1.0, 1.0) * jitter * object.distance.x,
+        );
+        sprite::push_ant(
+            batch,
+            ant_sheet,
+            pos + jitter,
+            ant.heading,
+            level_state.ant_size,
+            level_state.rules.ant_sprite(object, ant.idx),
+            Pose::Walking {
+                tick,
+                phase: ant.idx,
+            },
+        );
+    }
+    for corpse in &level_state.corpses {
+        sprite::push_ant(
+            batch,
+            ant_sheet,
+            corpse.pos,
+            corpse.heading,
+            level_state.ant_size,
+            corpse.look,
+            Pose::Dead,
+        );
     }
 
+    //draw drones
+    let drone_sprite = resources.sprite(&Drone).unwrap();
+    for drone in drone_positions(level_state) {
+        batch.push(drone_sprite, drone, drone_sprite.size(), 0.0, WHITE);
+    }
+    batch.flush();
+
     //draw money
-    draw_text(
+    draw_hud_text(
         &format!("Money: ${:.2}", level_state.money),
         10.0,
         20.0,
         20.0,
-        WHITE,
+        settings,
     );
-    draw_text(
+    draw_hud_text(
         &format!("Goal: ${}", level_state.money_goal),
         10.0,
         40.0,
         20.0,
-        WHITE,
+        settings,
     );
 
-    // shift duration (medium level)
-    if level_state.difficulty == Difficulty::Medium && duration.is_some() {
-        draw_text(
+    // shift duration, for the rules that have one
+    if let Some(shift_left) = level_state.shift_left() {
+        draw_hud_text(
+            &format!("Shift ends in: {:.3}s", shift_left as f32 / 60.0),
+            10.0,
+            60.0,
+            20.0,
+            settings,
+        );
+    }
+
+    // incidents that last a while, and how long they still do
+    for (idx, ongoing) in level_state.ongoing.iter().enumerate() {
+        let incident = &level_state.incidents[ongoing.incident];
+        draw_hud_text(
             &format!(
-                "Shift ends in: {:.3}s",
-                (MEDIUM_DURATION - duration.unwrap()) as f32 / 60.0
+                "{} {:.0}s",
+                incident.announcement,
+                ongoing.ticks_left as f32 / 60.0
             ),
             10.0,
-            60.0,
+            GAME_HEIGHT as f32 - 60.0 - idx as f32 * 20.0,
             20.0,
-            WHITE,
+            settings,
         );
     }
 
     //draw buy-repellant/drone button
-    let rep_btn_width = 170.0;
-    let rep_btn_height = 30.0;
-    let rep_btn_top = LineSegment::new((960.0 - rep_btn_width - 10.0, 10.0), (960.0 - 10.0, 10.0));
-    let rep_btn_btm = LineSegment::new(
-        (960.0 - rep_btn_width - 10.0, rep_btn_height + 10.0),
-        (960.0 - 10.0, rep_btn_height + 10.0),
-    );
-    draw_rectangle(
-        rep_btn_top.start.x,
-        rep_btn_top.start.y,
-        rep_btn_width,
-        rep_btn_height,
-        BLACK,
-    );
+    let rep_btn = rep_btn_rect();
+    draw_rectangle(rep_btn.x, rep_btn.y, rep_btn.w, rep_btn.h, BLACK);
     draw_text(
         &format!(
             "{} ${:.2}",
             level_state.repellant_name, level_state.repellant_price
         ),
-        rep_btn_top.start.x + 10.0,
-        rep_btn_top.start.y + 20.0,
+        rep_btn.x + 10.0,
+        rep_btn.y + 20.0,
         20.0,
         WHITE,
     );
-    draw_text(
+    draw_hud_text(
         &format!("Owned: [{}]", level_state.repellants),
-        rep_btn_top.start.x + 10.0,
-        rep_btn_top.start.y + 50.0,
+        rep_btn.x + 10.0,
+        rep_btn.y + 50.0,
         20.0,
-        WHITE,
+        settings,
     );
 
-    // buying supplements
-    if is_mouse_button_pressed(MouseButton::Left)
-        && rep_btn_top.lies_between(&rep_btn_btm, mouse_pos.into())
-        && level_state.money > level_state.repellant_price
-    {
-        level_state.repellants += 1;
-        level_state.money -= level_state.repellant_price;
-    }
+    //draw pause button
+    let pause_btn = pause_btn_rect();
+    draw_rectangle(pause_btn.x, pause_btn.y, pause_btn.w, pause_btn.h, BLACK);
+    let icon = resources.sprite(&IconPause).unwrap();
+    batch.push(icon, pause_btn.center(), icon.size(), 0.0, WHITE);
+    batch.flush();
+}
 
-    // change in state
-    if level_state.money > level_state.money_goal {
-        // You Won
-        // Some(Screen::Dialog(Dialog::Won(Stage::A1, Stage::B1)))
-        match level_state.difficulty {
-            Difficulty::Easy => 
-        Some(Screen::Dialog(Dialog::Story(StoryIter{
-            pages: vec![
-                (Story3, vec![
-                    "well done, you did it. you earned enough money to move to the center.".to_owned()]
-                ),
-                (Story3, vec![
-                    "you are now ant free. this feels like heaven.".to_owned()]
-                ),
-                (Story3, vec![
-                    "the center has some next-generation technology that makes the ants pass out".to_owned(),
-                    "for very long durations of time.".to_owned()]
-                ),
-                (Story3, vec![
-                    "they are still up in their labs looking for ways to kill an ant, i hear.".to_owned()]
-                ),
-                (Story3, vec![
-                    "however, the luxury has changed you.".to_owned()]
-                ),
-                (Story3, vec![
-                    "made you afraid of losing it.".to_owned()]
-                ),
-                (Story3, vec![
-                    "your new job pays very handsomly and the better you do your job,".to_owned(),
-                    "the more years you secure this life.".to_owned()]
-                ),
-                (Story3, vec![
-                    "you are loyal to the work you do and the people who pay you for it.".to_owned()]
-                ),
-                (Story3, vec![
-                    "you collect all the ants rendered unconscious by the machine and".to_owned(),
-                    "sneakily dump them outside for a living!".to_owned()]
-                ),
-                (Story3, vec![
-                    "the toxic substance used by the machine is not fit to be touched".to_owned(),
-                    "so you get to use drones to go deliver the bags of ants for you.".to_owned()]
-                ),
-                (Story3, vec![
-                    "you're in charge of a small street with only 3 inhabited houses.".to_owned()]
-                ),
-                (Story3, vec![
-                    "your job involves being careful that you go unnoticed, and this includes".to_owned(),
-                    "not making any of the residents suspicious.".to_owned()]
-                ),
-                (Story3, vec![
-                    "unloading too many ants as well as not unloading enough will bring suspicion.".to_owned()]
-                ),
-                (Story3, vec![
-                    "your shift starts as soon as the shift before you ends. get ready".to_owned()]
-                ),
-            ].into()
-        }, Stage::B1))),
-            Difficulty::Medium => Some(Screen::Dialog(Dialog::Thanks)),
+/// Parses `name` into `levels` or `stories` if it is a level or story file. `level_file` is put in
+/// place of the level file it stands in for.
+fn read_data(
+    levels: &mut HashMap<Stage, LevelState>,
+    stories: &mut HashMap<ResourceName, StoryIter>,
+    level_file: &Option<(Stage, LevelState)>,
+    resources: &Resources,
+    name: ResourceName,
+) -> Result<(), String> {
+    let stage = Stage::all().find(|stage| stage.info().level == name);
+    if let (Some(stage), Some((replaced, level))) = (stage, level_file) {
+        if stage == *replaced {
+            levels.insert(stage, level.clone());
+            return Ok(());
         }
-    } else if level_state.difficulty == Difficulty::Easy && num_objects == objects_complete_dirty {
-        // You Lost
-        // Some(Screen::DeathAnim(Stage::A1, 0))
-        Some(Screen::Dialog(Dialog::Lost(Stage::A1)))
-    } else if level_state.difficulty == Difficulty::Medium
-        && (objects_complete_dirty >= 1 || duration.filter(|d| *d > MEDIUM_DURATION).is_some())
-    {
-        *duration = Some(0);
-        Some(Screen::Dialog(Dialog::Lost(Stage::B1)))
-    } else {
-        None
     }
+    let Some(text) = resources.text(&name) else {
+        return Ok(());
+    };
+    if let Some(stage) = stage {
+        levels.insert(stage, data::parse_level(text)?);
+    } else if Stage::all().any(|stage| stage.info().story == name) {
+        stories.insert(name, data::parse_story(name, text)?);
+    }
+    Ok(())
 }
 
-impl DirtyObj {
-    fn new(
-        dirtiness: u8,
-        start: LineSegment,
-        end: LineSegment,
-        amount: usize,
-        chance_bidir: u8,
-    ) -> Self {
-        let distance =
-            ((end.start + end.end) / vec2(2.0, 2.0)) - ((start.start + start.end) / vec2(2.0, 2.0));
-        DirtyObj {
-            dirtiness,
-            start,
-            end,
-            amount,
-            distance,
-            chance_bidir,
+/// Puts a level or story file that changed on disk into whatever is on screen right now.
+fn apply_reload(state: &mut GameState, name: ResourceName) {
+    for screen in &mut state.screens {
+        match screen {
+            Screen::Stage(stage, level_state) if stage.info().level == name => {
+                level_state.reload(&state.levels[stage]);
+                // a replay of it would not match any more
+                state.recording = None;
+            }
+            Screen::Dialog(Dialog::Story(story_iter, _)) if story_iter.source == name => {
+                // stay on the same page, as far as the new file still has one
+                let mut story = state.stories[&name].clone();
+                let read = story.pages.len().saturating_sub(story_iter.pages.len());
+                story.pages.drain(..read);
+                story.revealed = story_iter.revealed;
+                *story_iter = story;
+            }
+            _ => {}
         }
     }
 }
 
-fn lerp_ant(
-    tick: usize,
-    object: &DirtyObj,
-    mut start: Vec2,
-    mut end: Vec2,
-    seed: usize,
-    randomize_end: bool,
-) -> Option<(f32, f32)> {
-    if (f32::powi(NOISE[seed % 100], 2) * object.dirtiness as f32) < 63.0 {
-        return None;
+fn main() {
+    let options = match Options::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{err}\n\n{}", cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+    if options.help {
+        println!("{}", cli::USAGE);
+        return;
     }
-    if NOISE[(seed + 1) % 100] * 255.0 < object.chance_bidir as f32 {
-        std::mem::swap(&mut start, &mut end);
+
+    let recording = options.replay.as_ref().map(|path| {
+        or_exit(
+            std::fs::read_to_string(path)
+                .map_err(|err| err.to_string())
+                .and_then(|source| Recording::parse(&source))
+                .map_err(|err| format!("could not read the replay {path}: {err}")),
+        )
+    });
+    // the stage played first, which is the one a level file stands in for
+    let stage = match &recording {
+        Some(recording) => recording.stage,
+        None => options.stage.unwrap_or(Stage::FIRST),
+    };
+    let level_file = options
+        .level_file
+        .as_ref()
+        .map(|path| (stage, or_exit(read_level_file(path))));
+
+    if !options.headless {
+        macroquad::Window::from_config(
+            conf(),
+            game(options, level_file, recording.map(Replay::new)),
+        );
+        return;
     }
-    if randomize_end {
-        end = end
-            + vec2(
-                (NOISE[(seed + 20) % 100] * 2.0 - 1.0) * STOE_SHIFT * object.distance.y,
-                (NOISE[(seed + 30) % 100] * 2.0 - 1.0) * STOE_SHIFT * object.distance.x,
-            );
+    let mut level = match level_file {
+        Some((_, level)) => level,
+        None => or_exit(read_level_file(assets::path(stage.info().level))),
+    };
+    match (recording, options.ticks) {
+        (Some(recording), _) => {
+            recording.run_headless(&mut level);
+            match recording.verify(&level) {
+                Ok(()) => println!("replay matches"),
+                Err(err) => {
+                    eprintln!("replay does not match: {err}");
+                    std::process::exit(1);
+                }
+            }
+        }
+        (None, Some(ticks)) => {
+            level.rng = options.seed.unwrap_or_default();
+            let ended = simulate(&mut level, ticks);
+            println!("{ended} at 'end {}'", Outcome::of(&level));
+        }
+        // the options make sure there is one or the other
+        (None, None) => unreachable!(),
     }
+}
 
-    let tick_x = tick + (NOISE[seed % 100] * 20.0 * object.amount as f32) as usize;
-    let tick_y = tick + (NOISE[seed % 100] * 20.0 * object.amount as f32) as usize;
+/// Stops the game over something asked for on the command line that cannot be done.
+fn or_exit<T>(result: Result<T, String>) -> T {
+    result.unwrap_or_else(|err| {
+        eprintln!("{err}");
+        std::process::exit(2);
+    })
+}
 
-    let dist_x = end.x - start.x;
-    let dist_y = end.y - start.y;
+fn read_level_file(path: &str) -> Result<LevelState, String> {
+    std::fs::read_to_string(path)
+        .map_err(|err| err.to_string())
+        .and_then(|source| data::parse_level(&source))
+        .map_err(|err| format!("{path}: {err}"))
+}
 
-    let x = start.x
-        + (((tick_x as f32 * 0.002) * (end.x - start.x))
-            % if dist_x == 0.0 { 1.0 } else { dist_x });
-    let y = start.y
-        + (((tick_y as f32 * 0.002) * (end.y - start.y))
-            % if dist_y == 0.0 { 1.0 } else { dist_y });
-    return Some((x, y));
+/// Runs up to `ticks` ticks with nothing clicked, saying how far the stage got.
+fn simulate(level: &mut LevelState, ticks: usize) -> &'static str {
+    let (mut events, mut cues) = (vec![], vec![]);
+    for _ in 0..ticks {
+        update_level(level, &[], &mut events, &mut cues);
+        for event in events.drain(..) {
+            match event {
+                Event::StageWon => return "won",
+                Event::StageLost => return "lost",
+                _ => {}
+            }
+        }
+        cues.clear();
+    }
+    "still playing"
 }
 
-#[macroquad::main(conf)]
-async fn main() {
+async fn game(options: Options, level_file: Option<(Stage, LevelState)>, replay: Option<Replay>) {
     use ResourceName::*;
-    let mut tick: usize = 0;
-    let mut duration = None;
-
-    let story1 = StoryIter{pages: vec![
-        (Story2, vec![
-            "the year is 2027. you had big expectations of this year back in 2024.".to_owned()
-        ]),
-        (Story2, vec![
-            "instead, you get an ant infestation epidemic. ants have gotten a lot more adaptive.".to_owned()
-        ]),
-        (Story2, vec![
-            "a person living with upper-class income can afford to avoid ants from infesting any".to_owned(),
-            "dust, food or sweat for 5 minutes.".to_owned()
-        ]),
-        (Story2, vec![
-            "I, on the other hand have to compromise for a grand 5 seconds.".to_owned()
-        ]),
-        (Story2, vec![
-            "the only way out from this anguish is to buy a place at the centre.".to_owned()
-        ]),
-        (Story2, vec![
-            "i have lots of clients in need of a website to advertise their".to_owned(),
-            "ant-repellant products.".to_owned()
-        ]),
-        (Story2, vec![
-            "all i need is to survive".to_owned()
-        ]),
-        (Story2, vec![
-            "its difficult to have hope in these times but a little energy and a little strategy".to_owned(),
-            "and i may be able to make it out.".to_owned()
-        ]),
-        (Story2, vec![
-            "i can buy repellant to slow down how fast these bad boys multiply.".to_owned(),
-        ]),
-        (Story2, vec![
-            "as long as they dont filth all of my belongings, ill make it.".to_owned(),
-        ]),
-        (Story2, vec![
-            "i make money every second that i am not busy tending to ants.".to_owned()
-        ]),
-    ].into()};
-
-    // resources
-    #[rustfmt::skip]
-    let resources = Resources::from([
-        (ImageA1, load_texture("./gimp/bg_a_1.png").await.unwrap()),
-        (ImageB1, load_texture("./gimp/bg_b_1.png").await.unwrap()),
-        (Ant, load_texture("./gimp/ant2.png").await.unwrap()),
-        (AntCrit, load_texture("./gimp/ant_crit.png").await.unwrap()),
-        (AntSV, load_texture("./gimp/ant3.png").await.unwrap()),
-        (AntSVCrit, load_texture("./gimp/ant3_crit.png").await.unwrap()),
-        (AntSH, load_texture("./gimp/ant4.png").await.unwrap()),
-        (AntSHCrit, load_texture("./gimp/ant3_crit.png").await.unwrap()),
-        (Story1, load_texture("./gimp/story3.png").await.unwrap()),
-        (Story2, load_texture("./gimp/story1.png").await.unwrap()),
-        (Story3, load_texture("./gimp/story3.png").await.unwrap()),
-    ]);
-
-    let levels = HashMap::from([
-        // level 1: killing ants
-        (
-            Stage::A1,
-            LevelState {
-                difficulty: Difficulty::Easy,
-                money: 0.0,
-                money_goal: 1_000.0,
-                repellants: 0,
-                repellant_name: "Repellant".to_owned(),
-                repellant_price: 4.0,
-                scene: ResourceName::ImageA1,
-                objects: vec![
-                    // coffee cup
-                    DirtyObj::new(
-                        64,
-                        LineSegment::new((179.0, 412.0), (223.0, 412.0)),
-                        LineSegment::new((186.0, 360.0), (215.0, 361.0)),
-                        200,
-                        0,
-                    ),
-                    // keyboard
-                    DirtyObj::new(
-                        64,
-                        LineSegment::new((250.0, 404.0), (590.0, 404.0)),
-                        LineSegment::new((278.0, 361.0), (575.0, 361.0)),
-                        700,
-                        128,
-                    ),
-                    // mouse
-                    DirtyObj::new(
-                        64,
-                        LineSegment::new((624.0, 369.0), (636.0, 391.0)),
-                        LineSegment::new((652.0, 355.0), (664.0, 378.0)),
-                        90,
-                        63,
-                    ),
-                ],
-            },
-        ),
-        (
-            Stage::B1,
-            LevelState {
-                difficulty: Difficulty::Medium,
-                money: 0.0,
-                money_goal: 10_000.0,
-                repellants: 1,
-                repellant_name: "Drones".to_owned(),
-                repellant_price: 100.0,
-                scene: ResourceName::ImageB1,
-                objects: vec![
-                    // house 1
-                    DirtyObj::new(
-                        163,
-                        LineSegment::new((332.0, 126.0), (404.0, 126.0)),
-                        LineSegment::new((332.0, 178.0), (404.0, 178.0)),
-                        500,
-                        63,
-                    ),
-                    // house 2
-                    DirtyObj::new(
-                        162,
-                        LineSegment::new((674.0, 196.0), (674.0, 262.0)),
-                        LineSegment::new((614.0, 220.0), (614.0, 222.0)),
-                        800,
-                        0,
-                    ),
-                    // house 3
-                    DirtyObj::new(
-                        163,
-                        LineSegment::new((332.0, 273.0), (404.0, 273.0)),
-                        LineSegment::new((332.0, 325.0), (404.0, 325.0)),
-                        500,
-                        225,
-                    ),
-                ],
-            },
-        ),
-    ]);
+    // resources, filled in by the loading screen
+    let mut resources = Resources::default();
+    #[cfg(all(debug_assertions, not(target_arch = "wasm32")))]
+    for unused in assets::unused_files() {
+        eprintln!("asset not in the manifest: {unused}");
+    }
+
+    input::init();
+    let mut settings = Settings::load();
+    if let Some(fullscreen) = options.fullscreen {
+        settings.fullscreen = fullscreen;
+    }
+    if settings.fullscreen {
+        set_fullscreen(true);
+    }
+    let mut letterbox = Letterbox::new(vec2(GAME_WIDTH as f32, GAME_HEIGHT as f32));
+    let mut jukebox = Jukebox::new(settings.volume);
+    let mut effects = Effects::new();
+    let mut batch = SpriteBatch::new();
+    let mut cues = Vec::new();
+    let mut events = Vec::new();
+    let mut watcher = Watcher::new();
+    // files were loaded during the last frame, which made it take long
+    let mut reloaded = false;
 
-    // levels
+    // levels and stories, filled in once their files are loaded
     let mut state = GameState {
-        // screen: Screen::DeathAnim(Stage::A1, 0),
-        // screen: Screen::Stage(Stage::B1, levels.get(&Stage::B1).unwrap().clone()),
-        screen: Screen::Dialog(Dialog::Story(story1, Stage::A1)),
-        // screen: Screen::Dialog(Dialog::Thanks),
-        levels,
+        screens: vec![Screen::Loading(Loader::new())],
+        transition: None,
+        levels: HashMap::new(),
+        stories: HashMap::new(),
+        settings,
+        seed: match (&replay, options.seed) {
+            (Some(replay), _) => replay.recording.seed,
+            (None, Some(seed)) => seed,
+            (None, None) => miniquad::date::now() as u64,
+        },
+        recording: None,
+        replay,
+        level_file,
+        skip_story: options.skip_story,
+        debug: options.debug,
     };
 
     loop {
+        if is_key_pressed(KeyCode::F11) {
+            state.settings.fullscreen = !state.settings.fullscreen;
+            set_fullscreen(state.settings.fullscreen);
+            state.settings.save();
+        }
+
+        letterbox.begin();
         clear_background(BLACK);
+        let pointers = input::pointers(&letterbox);
+        let tapped = input::any_pressed(&pointers);
 
-        let next_screen = match &mut state.screen {
-            Screen::MainMenu => {
-                if is_mouse_button_pressed(MouseButton::Left) {
-                    Some(Screen::Stage(
-                        Stage::A1,
-                        state.levels.get(&Stage::A1).unwrap().clone(),
-                    ))
-                } else {
-                    None
-                }
-            }
-            Screen::Dialog(dialog) => match dialog {
-                Dialog::Won(stage, next_stage) => {
-                    draw_text(&format!("You won stage {stage}"), 10.0, 20.0, 20.0, WHITE);
-                    if is_mouse_button_pressed(MouseButton::Left) {
-                        Some(Screen::Stage(
-                            *next_stage,
-                            state.levels.get(next_stage).unwrap().clone(),
-                        ))
+        // everything under the topmost screen that does not pass through only gets drawn
+        let running_from = state
+            .screens
+            .iter()
+            .rposition(|screen| !screen.passes_through())
+            .unwrap_or(0);
+        let mut changes = vec![];
+        for idx in 0..state.screens.len() {
+            let running = idx >= running_from && state.transition.is_none();
+            let (pointers, tapped) = match running {
+                true => (&pointers[..], tapped),
+                false => (&[][..], false),
+            };
+            let (below, screens) = state.screens.split_at_mut(idx);
+            let screen_change = match &mut screens[0] {
+                Screen::Loading(loader) => {
+                    draw_text(TITLE, 60.0, 200.0, 50.0, WHITE);
+                    draw_rectangle_lines(60.0, 240.0, 840.0, 24.0, 2.0, WHITE);
+                    draw_rectangle(64.0, 244.0, 832.0 * loader.progress(), 16.0, WHITE);
+                    if let Some(path) = loader.next_path() {
+                        draw_text(path, 60.0, 290.0, 20.0, GRAY);
+                    }
+
+                    // without every level and story there is nothing to play
+                    let playable = Stage::all().count() == state.levels.len()
+                        && Stage::all().count() == state.stories.len();
+                    if !loader.is_done() {
+                        loader.load_next(&mut resources).await;
+                        if loader.is_done() {
+                            for &(name, _, path) in assets::MANIFEST {
+                                if let Err(error) = read_data(
+                                    &mut state.levels,
+                                    &mut state.stories,
+                                    &state.level_file,
+                                    &resources,
+                                    name,
+                                ) {
+                                    eprintln!("failed to read {path}: {error}");
+                                    loader.errors.push(AssetError { name, path, error });
+                                }
+                            }
+                        }
+                        None
+                    } else if loader.errors.is_empty() || (playable && tapped) {
+                        let next_screen = match (&state.replay, options.stage) {
+                            (Some(replay), _) => {
+                                let stage = replay.recording.stage;
+                                Screen::Stage(stage, state.levels[&stage].clone())
+                            }
+                            (None, Some(stage)) => {
+                                enter_stage(&state.levels, &state.stories, state.skip_story, stage)
+                            }
+                            (None, None) => Screen::MainMenu,
+                        };
+                        Some(Change::Replace(next_screen))
                     } else {
+                        // the game still runs with placeholders, so let the player decide
+                        draw_text(
+                            &format!("{} file(s) failed to load:", loader.errors.len()),
+                            60.0,
+                            290.0,
+                            20.0,
+                            RED,
+                        );
+                        for (idx, error) in loader.errors.iter().enumerate() {
+                            draw_text(
+                                &error.to_string(),
+                                60.0,
+                                315.0 + idx as f32 * 20.0,
+                                16.0,
+                                RED,
+                            );
+                        }
+                        draw_text(
+                            match playable {
+                                true => "Click to continue anyway.",
+                                false => "Fix the files above and restart.",
+                            },
+                            60.0,
+                            520.0,
+                            20.0,
+                            WHITE,
+                        );
                         None
                     }
                 }
-                Dialog::Lost(stage) => {
-                    draw_text(match stage {
-                        Stage::A1 => "You lost. There were two many ants. You died a disgusting death. Click to try again.",
-                        Stage::B1 => "You lost. The people got suspicious and you were fired from your job. Click to try again."
-                    }, 10.0, 20.0, 20.0, WHITE);
-                    if is_mouse_button_pressed(MouseButton::Left) {
-                        Some(Screen::Stage(
-                            *stage,
-                            state.levels.get(stage).unwrap().clone(),
+                Screen::MainMenu => {
+                    draw_texture(resources.get(&Story2).unwrap(), 0.0, 0.0, WHITE);
+                    draw_text(TITLE, 60.0, 200.0, 50.0, WHITE);
+                    draw_text("Click to start.", 60.0, 250.0, 25.0, WHITE);
+                    tapped.then(|| {
+                        Change::Replace(enter_stage(
+                            &state.levels,
+                            &state.stories,
+                            state.skip_story,
+                            Stage::FIRST,
                         ))
-                    } else {
+                    })
+                }
+                Screen::Dialog(dialog) => match dialog {
+                    Dialog::Won(stage) => {
+                        let text = format!("{} Click to continue.", stage.info().won);
+                        draw_text(&text, 10.0, 20.0, 20.0, WHITE);
+                        tapped.then(|| {
+                            Change::Replace(after_win(
+                                &state.levels,
+                                &state.stories,
+                                state.skip_story,
+                                *stage,
+                            ))
+                        })
+                    }
+                    Dialog::Lost(stage) => {
+                        let text = format!("{} Click to try again.", stage.info().lost);
+                        draw_text(&text, 10.0, 20.0, 20.0, WHITE);
+                        tapped.then(|| {
+                            Change::Replace(Screen::Stage(*stage, state.levels[stage].clone()))
+                        })
+                    }
+                    Dialog::Story(story_iter, next_stage) => {
+                        if let Some((resource_name, page)) = story_iter.peek() {
+                            draw_texture(resources.get(&resource_name).unwrap(), 0.0, 0.0, WHITE);
+                            story_iter.revealed += state.settings.text_speed.chars_per_tick();
+                            let mut to_show = story_iter.revealed as usize;
+                            for (idx, line) in page.iter().enumerate() {
+                                let shown: String = line.chars().take(to_show).collect();
+                                to_show = to_show.saturating_sub(line.chars().count());
+                                draw_text(&shown, 30.0, 40.0 + idx as f32 * 30.0, 25.0, WHITE);
+                            }
+                            // first tap finishes typing out the page, the next one turns it
+                            if tapped {
+                                if to_show > 0 {
+                                    let _ = story_iter.next();
+                                } else {
+                                    story_iter.revealed = f32::INFINITY;
+                                }
+                            }
+                            None
+                        } else {
+                            Some(Change::Replace(Screen::Stage(
+                                *next_stage,
+                                state.levels[next_stage].clone(),
+                            )))
+                        }
+                    }
+                    Dialog::Thanks => {
+                        draw_texture(resources.get(&Story2).unwrap(), 0.0, 0.0, WHITE);
+                        draw_text("You had a great shift.", 60.0, 80.0, 35.0, WHITE);
+                        draw_text("Thanks for playing.", 60.0, 120.0, 35.0, WHITE);
+                        draw_text("Made by nigel", 60.0, 200.0, 30.0, WHITE);
                         None
                     }
+                },
+                // stands still the way it is, under an overlay or until it is covered up
+                Screen::Stage(stage, level_state) if !running => {
+                    draw_level(*stage, level_state, &resources, &state.settings, &mut batch);
+                    None
                 }
-                Dialog::Story(story_iter, next_stage) => {
-                    if let Some((resource_name, page)) = story_iter.peek() {
-                        draw_texture(resources.get(&resource_name).unwrap(), 0.0, 0.0, WHITE);
-                        for (idx, line) in page.iter().enumerate() {
-                            draw_text(&line, 30.0, 40.0 + idx as f32 * 30.0, 25.0, WHITE);
+                Screen::Stage(stage, level_state) => {
+                    // a replay takes no input at all, not even to pause
+                    let replayed = state.replay.as_mut().and_then(Replay::next_tick);
+                    // a frame this long means the window was hidden or the tab was in the
+                    // background, which is the closest thing to losing focus we get told about.
+                    // macroquad does not pass on miniquad's minimized and restored events
+                    if state.replay.is_none()
+                        && (is_key_pressed(KeyCode::Escape)
+                            || (get_frame_time() > 0.5 && !reloaded)
+                            || pointers
+                                .iter()
+                                .any(|p| p.pressed && pause_btn_rect().contains(p.pos)))
+                    {
+                        draw_level(*stage, level_state, &resources, &state.settings, &mut batch);
+                        Some(Change::Push(Screen::Pause(PauseMenu::Main, *stage)))
+                    } else {
+                        let pointers = replayed.as_deref().unwrap_or(pointers);
+                        update_level(level_state, pointers, &mut events, &mut cues);
+                        if let Some(recording) = &mut state.recording {
+                            recording.record(pointers);
                         }
-                        if is_mouse_button_pressed(MouseButton::Left) {
-                            let _ = story_iter.next();
-                            None
-                        } else {
+                        draw_level(*stage, level_state, &resources, &state.settings, &mut batch);
+                        // particles live outside the simulation. drawing them also moves them on,
+                        // so while the stage stands still they are not drawn at all, and carry on
+                        // from where they were once it runs again
+                        if !state.settings.reduce_motion {
+                            for cue in &cues {
+                                if let Cue::Effect(effect, pos) = cue {
+                                    effects.spawn(*effect, *pos);
+                                }
+                            }
+                            for event in &events {
+                                if let Some((effect, pos)) = event_effect(level_state, event) {
+                                    effects.spawn(effect, pos);
+                                }
+                            }
+                        }
+                        let drones = drone_positions(level_state);
+                        match level_state.effects.contains(&Effect::Exhaust) {
+                            true => effects.draw(&drones),
+                            false => effects.draw(&[]),
+                        }
+                        if state.debug {
+                            draw_debug(level_state, pointers);
+                        }
+                        let next_screen = stage_over(
+                            *stage,
+                            &state.levels,
+                            &state.stories,
+                            state.skip_story,
+                            &events,
+                        );
+                        if next_screen.is_some() {
+                            if let Some(recording) = state.recording.take() {
+                                recording.finish(level_state);
+                            }
+                        }
+                        if let Some(replay) = state.replay.as_ref().filter(|r| r.is_done()) {
+                            // exits the way a headless run does, so either can be scripted
+                            match replay.recording.verify(level_state) {
+                                Ok(()) => {
+                                    println!("replay matches");
+                                    order_quit();
+                                }
+                                Err(err) => {
+                                    eprintln!("replay does not match: {err}");
+                                    std::process::exit(1);
+                                }
+                            }
+                        }
+                        next_screen.map(Change::Replace)
+                    }
+                }
+                Screen::Pause(menu, stage) => {
+                    match menu.manage(*stage, &mut state.settings, pointers) {
+                        Some(PauseAction::Resume) => Some(Change::Close),
+                        Some(PauseAction::Restart) => Some(Change::Replace(Screen::Stage(
+                            *stage,
+                            state.levels[stage].clone(),
+                        ))),
+                        Some(PauseAction::Quit) => {
+                            order_quit();
                             None
                         }
-                    } else {
-                        Some(Screen::Stage(
-                            *next_stage,
-                            state.levels.get(next_stage).unwrap().clone(),
-                        ))
+                        None => None,
                     }
                 }
-                Dialog::Thanks => {
-                    draw_texture(resources.get(&Story2).unwrap(), 0.0, 0.0, WHITE);
-                    draw_text("You had a great shift.", 60.0, 80.0, 35.0, WHITE);
-                    draw_text("Thanks for playing.", 60.0, 120.0, 35.0, WHITE);
-                    draw_text("Made by nigel", 60.0, 200.0, 30.0, WHITE);
-                    None
+                Screen::Banner { text, frames_left } => {
+                    let alpha = (*frames_left as f32 / 30.0).min(1.0);
+                    let size = measure_text(text, None, 40, 1.0);
+                    draw_text(
+                        text,
+                        (GAME_WIDTH as f32 - size.width) / 2.0,
+                        GAME_HEIGHT as f32 / 2.0,
+                        40.0,
+                        Color::new(1.0, 1.0, 1.0, alpha),
+                    );
+                    // not held up by anything over it, there is nothing to miss
+                    *frames_left = frames_left.saturating_sub(1);
+                    (*frames_left == 0).then_some(Change::Close)
                 }
-            },
-            Screen::Stage(_stage, ref mut level_state) => {
-                manage_level(level_state, &resources, tick, &mut duration)
+                Screen::Tutorial(tutorial, stage) => {
+                    let level_state = below.iter().rev().find_map(|screen| match screen {
+                        Screen::Stage(_, level_state) => Some(level_state),
+                        _ => None,
+                    });
+                    let skipped = menu::button("Skip tutorial", skip_btn_rect(), pointers);
+                    if let (Some(level_state), false) = (level_state, skipped) {
+                        tutorial.update(level_state, tapped);
+                        if let Some(step) = tutorial.step() {
+                            draw_tutorial_step(step, level_state, &state.settings);
+                        }
+                    }
+                    (skipped || tutorial.is_done()).then(|| {
+                        state.settings.tutorials_done.push(*stage);
+                        state.settings.save();
+                        Change::Close
+                    })
+                }
+            };
+            if let Some(change) = screen_change {
+                changes.push((idx, change));
+            }
+        }
+
+        // a screen asking to be left again while it is being left is already on its way. the
+        // top goes first, so closing a screen does not move any of the ones below
+        for (idx, change) in changes.into_iter().rev() {
+            if state.transition.is_some() {
+                break;
+            }
+            match change {
+                Change::Replace(next_screen) => {
+                    let kind = transition_kind(&state.screens[idx], &next_screen, &state.settings);
+                    state.transition = Some(Transition::new(kind, next_screen));
+                }
+                Change::Push(screen) => state.screens.push(screen),
+                Change::Close => {
+                    state.screens.remove(idx);
+                }
+            }
+        }
+        if let Some(transition) = &mut state.transition {
+            transition.draw(&resources, &mut batch);
+            if let Some(next_screen) = transition.advance() {
+                state.screens.clear();
+                // every stage is played from the same seed, so its replay looks the same too
+                if let Screen::Stage(stage, mut level_state) = next_screen {
+                    macroquad::rand::srand(state.seed);
+                    level_state.rng = state.seed;
+                    if state.replay.is_none() {
+                        state.recording = Some(Recording::new(state.seed, stage));
+                    }
+                    state.screens.push(Screen::Stage(stage, level_state));
+                    state.screens.push(Screen::Banner {
+                        text: format!("Stage {stage}: {}", stage.info().name),
+                        frames_left: BANNER_FRAMES,
+                    });
+                    // like the stories, left out of replays and runs that skip them
+                    if !stage.info().tutorial.is_empty()
+                        && !state.settings.tutorials_done.contains(&stage)
+                        && state.replay.is_none()
+                        && !state.skip_story
+                    {
+                        let tutorial = Tutorial::new(stage.info().tutorial);
+                        state.screens.push(Screen::Tutorial(tutorial, stage));
+                    }
+                } else {
+                    state.screens.push(next_screen);
+                }
+            }
+            if transition.is_done() {
+                state.transition = None;
+            }
+        }
+
+        jukebox.set_volume(&resources, state.settings.volume);
+        // whatever is over the stage, its music keeps playing
+        let stage = state.screens.iter().find_map(|screen| match screen {
+            Screen::Stage(stage, _) => Some(*stage),
+            _ => None,
+        });
+        jukebox.set_music(&resources, stage.map(|stage| stage.info().music));
+        for cue in cues.drain(..) {
+            if let Cue::Sound(sound) = cue {
+                jukebox.play(&resources, &sound);
+            }
+        }
+        for event in events.drain(..) {
+            if let Some(sound) = event.sound() {
+                jukebox.play(&resources, &sound);
+            }
+            let level_state = state.screens.iter().find_map(|screen| match screen {
+                Screen::Stage(_, level_state) => Some(level_state),
+                _ => None,
+            });
+            if let (Event::IncidentStarted(idx), Some(level_state)) = (event, level_state) {
+                let text = level_state.incidents[idx].announcement.clone();
+                state.screens.push(Screen::Banner {
+                    text,
+                    frames_left: BANNER_FRAMES,
+                });
+            }
+        }
+
+        let changed = watcher.changed();
+        reloaded = !changed.is_empty();
+        for (name, kind, path) in changed {
+            match assets::load_entry(&mut resources, name, kind, path).await {
+                Ok(()) => {
+                    if let AssetKind::Sprite = kind {
+                        resources.pack_sprites();
+                    }
+                    match read_data(
+                        &mut state.levels,
+                        &mut state.stories,
+                        &state.level_file,
+                        &resources,
+                        name,
+                    ) {
+                        Ok(()) => {
+                            eprintln!("reloaded {path}");
+                            apply_reload(&mut state, name);
+                        }
+                        Err(error) => eprintln!("failed to read {path}: {error}"),
+                    }
+                }
+                Err(error) => eprintln!("failed to reload {path}: {error}"),
             }
-        };
-        if let Some(next_screen) = next_screen {
-            state.screen = next_screen;
         }
 
-        // draw_rectangle(179.0, 412.0, 50.0, 50.0, WHITE);
-        // let _ = draw_text("You Won!\nYou Lost!", 179.0, 412.0, 40.0, WHITE);
+        letterbox.present();
 
-        tick += 1;
         next_frame().await;
     }
 }
@@ -0,0 +1,143 @@
+use macroquad::{
+    color::*,
+    input::{is_key_pressed, KeyCode},
+    math::Rect,
+    shapes::{draw_rectangle, draw_rectangle_lines},
+    text::draw_text,
+    window::set_fullscreen,
+};
+
+use crate::{input::Pointer, settings::Settings, Stage, GAME_HEIGHT, GAME_WIDTH};
+
+const BUTTON_WIDTH: f32 = 320.0;
+const BUTTON_HEIGHT: f32 = 40.0;
+
+/// Draws a button and tells whether a pointer pressed it this frame.
+pub fn button(label: &str, rect: Rect, pointers: &[Pointer]) -> bool {
+    draw_rectangle(rect.x, rect.y, rect.w, rect.h, BLACK);
+    draw_rectangle_lines(rect.x, rect.y, rect.w, rect.h, 2.0, WHITE);
+    draw_text(label, rect.x + 12.0, rect.y + rect.h / 2.0 + 7.0, 25.0, WHITE);
+    pointers.iter().any(|p| p.pressed && rect.contains(p.pos))
+}
+
+/// The `idx`th button of a column centered on the screen.
+fn menu_rect(idx: usize) -> Rect {
+    Rect::new(
+        (GAME_WIDTH as f32 - BUTTON_WIDTH) / 2.0,
+        150.0 + idx as f32 * (BUTTON_HEIGHT + 15.0),
+        BUTTON_WIDTH,
+        BUTTON_HEIGHT,
+    )
+}
+
+fn draw_title(title: &str) {
+    draw_text(title, (GAME_WIDTH as f32 - BUTTON_WIDTH) / 2.0, 120.0, 40.0, WHITE);
+}
+
+/// Smaller and above the title.
+fn draw_subtitle(subtitle: &str) {
+    draw_text(subtitle, (GAME_WIDTH as f32 - BUTTON_WIDTH) / 2.0, 75.0, 25.0, GRAY);
+}
+
+pub enum PauseAction {
+    Resume,
+    Restart,
+    Quit,
+}
+
+/// Overlay shown over a frozen stage.
+pub enum PauseMenu {
+    Main,
+    Settings,
+}
+
+impl PauseMenu {
+    pub fn manage(
+        &mut self,
+        stage: Stage,
+        settings: &mut Settings,
+        pointers: &[Pointer],
+    ) -> Option<PauseAction> {
+        // dim the stage underneath
+        draw_rectangle(
+            0.0,
+            0.0,
+            GAME_WIDTH as f32,
+            GAME_HEIGHT as f32,
+            Color::new(0.0, 0.0, 0.0, 0.6),
+        );
+
+        match self {
+            PauseMenu::Main => {
+                draw_subtitle(&format!("Stage {stage}: {}", stage.info().name));
+                draw_title("Paused");
+                if button("Resume", menu_rect(0), pointers) || is_key_pressed(KeyCode::Escape) {
+                    return Some(PauseAction::Resume);
+                }
+                if button("Restart stage", menu_rect(1), pointers) {
+                    return Some(PauseAction::Restart);
+                }
+                if button("Settings", menu_rect(2), pointers) {
+                    *self = PauseMenu::Settings;
+                }
+                // closing the tab is the only way out on the web
+                #[cfg(not(target_arch = "wasm32"))]
+                if button("Quit", menu_rect(3), pointers) {
+                    return Some(PauseAction::Quit);
+                }
+                None
+            }
+            PauseMenu::Settings => {
+                draw_title("Settings");
+                if button(
+                    &format!("Volume: {:.0}%", settings.volume * 100.0),
+                    menu_rect(0),
+                    pointers,
+                ) {
+                    settings.next_volume();
+                }
+                if button(
+                    &format!("Text speed: {}", settings.text_speed),
+                    menu_rect(1),
+                    pointers,
+                ) {
+                    settings.text_speed = settings.text_speed.next();
+                }
+                if button(
+                    &format!("Fullscreen: {}", on_off(settings.fullscreen)),
+                    menu_rect(2),
+                    pointers,
+                ) {
+                    settings.fullscreen = !settings.fullscreen;
+                    set_fullscreen(settings.fullscreen);
+                }
+                if button(
+                    &format!("Reduce motion: {}", on_off(settings.reduce_motion)),
+                    menu_rect(3),
+                    pointers,
+                ) {
+                    settings.reduce_motion = !settings.reduce_motion;
+                }
+                if button(
+                    &format!("High contrast: {}", on_off(settings.high_contrast)),
+                    menu_rect(4),
+                    pointers,
+                ) {
+                    settings.high_contrast = !settings.high_contrast;
+                }
+                if button("Back", menu_rect(5), pointers) || is_key_pressed(KeyCode::Escape) {
+                    settings.save();
+                    *self = PauseMenu::Main;
+                }
+                None
+            }
+        }
+    }
+}
+
+fn on_off(b: bool) -> &'static str {
+    match b {
+        true => "on",
+        false => "off",
+    }
+}
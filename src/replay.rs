@@ -0,0 +1,297 @@
+//! Recording what the pointers did on every tick of a stage, and playing it back to reproduce
+//! how the stage went.
+//!
+//! A replay file is text, one `key values` line at a time:
+//!
+//! ```text
+//! seed 1712345678
+//! stage A1
+//! tick 480 270 1 1
+//! tick 482 270 0 1
+//! end 2 14.5 0 170 64 66
+//! ```
+//!
+//! `tick` lines hold `x y pressed down` for every pointer there was on that tick. `end` is how
+//! the stage was left, see `Outcome`. Only ticks that were simulated are recorded, so pausing
+//! does not show up. Replays start from the stage's level file as it is when played back, and
+//! one that was hot reloaded during recording will not match.
+
+use std::fmt;
+
+use macroquad::math::vec2;
+
+use crate::{
+    input::Pointer,
+    level::{update_level, LevelState},
+    stages::Stage,
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+const REPLAY_PATH: &str = "./replay.txt";
+
+/// What is compared to tell whether a replay went the same way as when it was recorded.
+#[derive(Debug, PartialEq)]
+pub struct Outcome {
+    tick: usize,
+    money: f32,
+    repellants: u16,
+    dirtiness: Vec<u8>,
+}
+
+impl Outcome {
+    pub fn of(level: &LevelState) -> Self {
+        Outcome {
+            tick: level.tick,
+            money: level.money,
+            repellants: level.repellants,
+            dirtiness: level
+                .objects
+                .iter()
+                .map(|object| object.dirtiness)
+                .collect(),
+        }
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // floats are written so they read back the same
+        write!(f, "{} {} {}", self.tick, self.money, self.repellants)?;
+        for dirtiness in &self.dirtiness {
+            write!(f, " {dirtiness}")?;
+        }
+        Ok(())
+    }
+}
+
+pub struct Recording {
+    /// what `macroquad::rand` was seeded with
+    pub seed: u64,
+    pub stage: Stage,
+    /// every pointer on every tick, in order
+    ticks: Vec<Vec<Pointer>>,
+    /// set once the stage is over
+    outcome: Option<Outcome>,
+}
+
+impl Recording {
+    pub fn new(seed: u64, stage: Stage) -> Self {
+        Recording {
+            seed,
+            stage,
+            ticks: vec![],
+            outcome: None,
+        }
+    }
+
+    pub fn record(&mut self, pointers: &[Pointer]) {
+        self.ticks.push(pointers.to_vec());
+    }
+
+    /// Notes how the stage ended and writes the recording out, over the last one.
+    pub fn finish(mut self, level: &LevelState) {
+        self.outcome = Some(Outcome::of(level));
+        write_replay_file(&self.to_string());
+    }
+
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let mut recording = Recording::new(0, Stage::FIRST);
+        for (idx, line) in source.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            recording
+                .parse_line(line)
+                .map_err(|err| format!("line {}: {err}", idx + 1))?;
+        }
+        Ok(recording)
+    }
+
+    fn parse_line(&mut self, line: &str) -> Result<(), String> {
+        let mut values = line.split_whitespace();
+        let key = values.next().unwrap_or_default();
+        let numbers = |values: std::str::SplitWhitespace| {
+            values
+                .map(|value| value.parse::<f32>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|err| format!("{key}: {err}"))
+        };
+        match key {
+            "seed" => {
+                self.seed = values
+                    .next()
+                    .and_then(|value| value.parse().ok())
+                    .ok_or("seed needs a whole number")?;
+            }
+            "stage" => {
+                let name = values.next().unwrap_or_default();
+                self.stage = Stage::parse(name).ok_or_else(|| format!("unknown stage '{name}'"))?;
+            }
+            "tick" => {
+                let numbers = numbers(values)?;
+                if numbers.len() % 4 != 0 {
+                    return Err("tick needs 'x y pressed down' for every pointer".to_owned());
+                }
+                self.ticks.push(
+                    numbers
+                        .chunks(4)
+                        .map(|pointer| Pointer {
+                            pos: vec2(pointer[0], pointer[1]),
+                            pressed: pointer[2] != 0.0,
+                            down: pointer[3] != 0.0,
+                        })
+                        .collect(),
+                );
+            }
+            "end" => {
+                let numbers = numbers(values)?;
+                if numbers.len() < 3 {
+                    return Err("end needs 'tick money repellants' and the dirtiness".to_owned());
+                }
+                self.outcome = Some(Outcome {
+                    tick: numbers[0] as usize,
+                    money: numbers[1],
+                    repellants: numbers[2] as u16,
+                    dirtiness: numbers[3..].iter().map(|d| *d as u8).collect(),
+                });
+            }
+            _ => return Err(format!("unknown key '{key}'")),
+        }
+        Ok(())
+    }
+
+    /// Whether `level`, after the whole replay, ended up the way it did when recorded.
+    pub fn verify(&self, level: &LevelState) -> Result<(), String> {
+        let outcome = Outcome::of(level);
+        match &self.outcome {
+            None => Err("the recording has no end to compare with".to_owned()),
+            Some(expected) if *expected == outcome => Ok(()),
+            Some(expected) => Err(format!(
+                "expected 'end {expected}' but the replay ended at 'end {outcome}'"
+            )),
+        }
+    }
+
+    /// Runs every recorded tick on `level` without drawing anything.
+    pub fn run_headless(&self, level: &mut LevelState) {
+        level.rng = self.seed;
+        let (mut events, mut cues) = (vec![], vec![]);
+        for pointers in &self.ticks {
+            update_level(level, pointers, &mut events, &mut cues);
+            events.clear();
+            cues.clear();
+        }
+    }
+}
+
+impl fmt::Display for Recording {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "seed {}", self.seed)?;
+        writeln!(f, "stage {}", self.stage)?;
+        for pointers in &self.ticks {
+            f.write_str("tick")?;
+            for pointer in pointers {
+                write!(
+                    f,
+                    " {} {} {} {}",
+                    pointer.pos.x, pointer.pos.y, pointer.pressed as u8, pointer.down as u8
+                )?;
+            }
+            writeln!(f)?;
+        }
+        if let Some(outcome) = &self.outcome {
+            writeln!(f, "end {outcome}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Plays a recording back on screen, one tick per frame.
+pub struct Replay {
+    pub recording: Recording,
+    next: usize,
+}
+
+impl Replay {
+    pub fn new(recording: Recording) -> Self {
+        Replay { recording, next: 0 }
+    }
+
+    /// The pointers of the next tick, `None` once all of them were played.
+    pub fn next_tick(&mut self) -> Option<Vec<Pointer>> {
+        let pointers = self.recording.ticks.get(self.next)?.clone();
+        self.next += 1;
+        Some(pointers)
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.next == self.recording.ticks.len()
+    }
+}
+
+// like the settings, the web build has nowhere to put replays
+#[cfg(not(target_arch = "wasm32"))]
+fn write_replay_file(contents: &str) {
+    if let Err(err) = std::fs::write(REPLAY_PATH, contents) {
+        eprintln!("could not save the replay to {REPLAY_PATH}: {err}");
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write_replay_file(_contents: &str) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        level::rep_btn_rect,
+        testing::{level, OBJECT},
+    };
+
+    const LEVEL: &str = "difficulty easy\nmoney_goal 5\nrepellant_price 1\n";
+
+    #[test]
+    fn a_written_recording_replays_the_same() {
+        let mut recorded = level(&format!("{LEVEL}{OBJECT}"));
+        let mut recording = Recording::new(7, Stage::FIRST);
+        recorded.rng = 7;
+        let (mut events, mut cues) = (vec![], vec![]);
+        for tick in 0..60 {
+            // clicking on the object for a while, then on the repellant button once
+            let pointers = [Pointer {
+                pos: match tick {
+                    45 => rep_btn_rect().center(),
+                    _ => vec2(5.0, tick as f32 / 4.0),
+                },
+                pressed: tick % 10 == 0 || tick == 45,
+                down: tick < 30 || tick == 45,
+            }];
+            update_level(&mut recorded, &pointers, &mut events, &mut cues);
+            recording.record(&pointers);
+        }
+        recording.outcome = Some(Outcome::of(&recorded));
+
+        let parsed = Recording::parse(&recording.to_string()).unwrap();
+        assert_eq!(parsed.seed, 7);
+        let mut replayed = level(&format!("{LEVEL}{OBJECT}"));
+        parsed.run_headless(&mut replayed);
+        assert_eq!(parsed.verify(&replayed), Ok(()));
+        assert_eq!(replayed.repellants, 1);
+    }
+
+    #[test]
+    fn a_different_level_does_not_verify() {
+        let mut recording = Recording::new(0, Stage::FIRST);
+        recording.record(&[]);
+        let mut played = level(&format!("{LEVEL}{OBJECT}"));
+        recording.run_headless(&mut played);
+        recording.outcome = Some(Outcome::of(&played));
+        let mut other = level(&format!(
+            "{LEVEL}{}",
+            OBJECT.replace("object 100", "object 90")
+        ));
+        recording.run_headless(&mut other);
+        assert!(recording.verify(&other).is_err());
+    }
+}
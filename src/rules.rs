@@ -0,0 +1,211 @@
+//! What a stage is about: how its objects get dirty, what a click does, where the money comes from
+//! and how it is won or lost.
+//!
+//! Level files pick a ruleset by name with their `difficulty` line. A new kind of stage is a new
+//! `Ruleset` added to `RULESETS`.
+
+use crate::{
+    assets::ResourceName,
+    level::{DirtyObj, LevelState, ANT_CELL, MAX_DIRTINESS, NOISE, WARN_DIRTINESS},
+    sprite::AntLook,
+};
+
+/// Every ruleset a level file can ask for.
+const RULESETS: [&dyn Ruleset; 2] = [&Easy, &Medium];
+
+/// Looks up the ruleset level files call `name`.
+pub fn by_name(name: &str) -> Option<&'static dyn Ruleset> {
+    RULESETS.into_iter().find(|rules| rules.name() == name)
+}
+
+/// How far from a pointer ants are hit, unless a ruleset says otherwise.
+const BASE_REACH: f32 = 8.0;
+
+pub trait Ruleset: Sync {
+    /// What level files call this ruleset.
+    fn name(&self) -> &'static str;
+
+    /// Lets the dirt come or go by itself, called for every object on every tick.
+    fn on_tick(&self, object: &mut DirtyObj, tick: usize, repellants: u16);
+
+    /// Called once per tick for every pointer held on `object`.
+    fn on_click(&self, object: &mut DirtyObj);
+
+    /// Money made on an object this tick, once for every pointer held on it if `clicked`, or
+    /// once if nothing is held anywhere.
+    fn income(&self, clicked: bool, repellants: u16) -> f32;
+
+    /// Checked after every tick, before `is_lost`.
+    fn is_won(&self, level: &LevelState) -> bool {
+        level.money > level.money_goal
+    }
+
+    /// Checked after every tick, the stage is also lost once its `shift_length` is over.
+    fn is_lost(&self, level: &LevelState) -> bool;
+
+    /// The dirtiness objects are to be kept within, shown on their gauges. `is_lost` is about
+    /// going past these, on one object or on all of them, as narrowed by incidents through
+    /// `LevelState::dirtiness_bounds`.
+    fn dirtiness_bounds(&self) -> (u8, u8) {
+        (0, MAX_DIRTINESS)
+    }
+
+    /// Which row of the spritesheet ant `seed` of `object` is drawn from.
+    fn ant_sprite(&self, object: &DirtyObj, seed: usize) -> AntLook;
+
+    /// Heard every so often while clicking on an object.
+    fn click_sound(&self) -> ResourceName;
+
+    /// How far from a pointer ants are hit.
+    fn reach(&self, _repellants: u16) -> f32 {
+        BASE_REACH
+    }
+
+    /// Whether ants spread out over the whole end segment instead of heading for the point
+    /// opposite of where they started.
+    fn scatter_ants(&self) -> bool {
+        false
+    }
+
+    /// Ticks the stage lasts at most, shown as a countdown.
+    fn shift_length(&self) -> Option<usize> {
+        None
+    }
+
+    /// How many of the repellants are drawn flying over the scene.
+    fn drones(&self, _repellants: u16) -> u16 {
+        0
+    }
+}
+
+/// Shows up as critical once the object is about to be overrun, for the noisiest
+/// `1 - critical_share` of the ants, in the look of `normal` or `critical`.
+fn look_by_share(
+    object: &DirtyObj,
+    seed: usize,
+    critical_share: f32,
+    (normal, critical): (AntLook, AntLook),
+) -> AntLook {
+    match object.dirtiness > WARN_DIRTINESS && NOISE[seed % 100] > critical_share {
+        true => critical,
+        false => normal,
+    }
+}
+
+/// A1: keeping the ants out of the house, clicking kills them.
+pub struct Easy;
+
+const REACH_PER_REPELLANT: f32 = 2.0;
+const MAX_REACH: f32 = ANT_CELL;
+
+impl Ruleset for Easy {
+    fn name(&self) -> &'static str {
+        "easy"
+    }
+
+    fn on_tick(&self, object: &mut DirtyObj, tick: usize, repellants: u16) {
+        // repellant slows the ants down
+        let rep_effect = (3.0 * (repellants as f32 / 10.0)) as usize;
+        if tick.is_multiple_of(7 + rep_effect) {
+            object.dirtiness = object.dirtiness.saturating_add(1);
+        }
+    }
+
+    fn on_click(&self, object: &mut DirtyObj) {
+        object.dirtiness = object.dirtiness.saturating_sub(1);
+    }
+
+    fn income(&self, clicked: bool, _repellants: u16) -> f32 {
+        // paid for the time not spent cleaning
+        match clicked {
+            true => 0.0,
+            false => 0.1,
+        }
+    }
+
+    fn is_lost(&self, level: &LevelState) -> bool {
+        level
+            .objects
+            .iter()
+            .enumerate()
+            .all(|(idx, object)| object.dirtiness > level.dirtiness_bounds(idx).1)
+    }
+
+    fn ant_sprite(&self, object: &DirtyObj, seed: usize) -> AntLook {
+        look_by_share(object, seed, 0.9, (AntLook::Normal, AntLook::Critical))
+    }
+
+    fn click_sound(&self) -> ResourceName {
+        ResourceName::Squish
+    }
+
+    fn reach(&self, repellants: u16) -> f32 {
+        (BASE_REACH + REACH_PER_REPELLANT * repellants as f32).min(MAX_REACH)
+    }
+
+    fn scatter_ants(&self) -> bool {
+        true
+    }
+}
+
+/// B1: sneaking ants into other houses, clicking sends them in.
+pub struct Medium;
+
+/// A house with fewer ants than this is noticed to be getting cleaned up.
+const MIN_DIRTINESS: u8 = 40;
+
+impl Ruleset for Medium {
+    fn name(&self) -> &'static str {
+        "medium"
+    }
+
+    fn on_tick(&self, object: &mut DirtyObj, tick: usize, _repellants: u16) {
+        if tick.is_multiple_of(7) {
+            object.dirtiness = object.dirtiness.saturating_sub(1);
+        }
+    }
+
+    fn on_click(&self, object: &mut DirtyObj) {
+        object.dirtiness = object.dirtiness.saturating_add(1);
+    }
+
+    fn income(&self, clicked: bool, repellants: u16) -> f32 {
+        match clicked {
+            true => 0.5 + 2.0 * (repellants as f32).ln(),
+            false => 0.0,
+        }
+    }
+
+    fn is_lost(&self, level: &LevelState) -> bool {
+        level.objects.iter().enumerate().any(|(idx, object)| {
+            let (min, max) = level.dirtiness_bounds(idx);
+            object.dirtiness > max || object.dirtiness < min
+        })
+    }
+
+    fn dirtiness_bounds(&self) -> (u8, u8) {
+        (MIN_DIRTINESS, MAX_DIRTINESS)
+    }
+
+    fn ant_sprite(&self, object: &DirtyObj, seed: usize) -> AntLook {
+        // the houses are seen from across the street
+        look_by_share(
+            object,
+            seed,
+            0.7,
+            (AntLook::Distant, AntLook::DistantCritical),
+        )
+    }
+
+    fn click_sound(&self) -> ResourceName {
+        ResourceName::Deposit
+    }
+
+    fn shift_length(&self) -> Option<usize> {
+        Some(4 * 60 * 60)
+    }
+
+    fn drones(&self, repellants: u16) -> u16 {
+        repellants
+    }
+}
@@ -0,0 +1,167 @@
+use std::fmt;
+
+use clicker1::stages::Stage;
+
+#[cfg(not(target_arch = "wasm32"))]
+const SETTINGS_PATH: &str = "./settings.txt";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TextSpeed {
+    Slow,
+    Normal,
+    Fast,
+    Instant,
+}
+
+impl TextSpeed {
+    /// How many characters of a story page appear per frame.
+    pub fn chars_per_tick(self) -> f32 {
+        match self {
+            TextSpeed::Slow => 0.5,
+            TextSpeed::Normal => 1.0,
+            TextSpeed::Fast => 2.5,
+            TextSpeed::Instant => f32::INFINITY,
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            TextSpeed::Slow => TextSpeed::Normal,
+            TextSpeed::Normal => TextSpeed::Fast,
+            TextSpeed::Fast => TextSpeed::Instant,
+            TextSpeed::Instant => TextSpeed::Slow,
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "slow" => Some(TextSpeed::Slow),
+            "normal" => Some(TextSpeed::Normal),
+            "fast" => Some(TextSpeed::Fast),
+            "instant" => Some(TextSpeed::Instant),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for TextSpeed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TextSpeed::Slow => "slow",
+            TextSpeed::Normal => "normal",
+            TextSpeed::Fast => "fast",
+            TextSpeed::Instant => "instant",
+        })
+    }
+}
+
+/// Player preferences, kept in `settings.txt` as `key=value` lines.
+#[derive(Clone)]
+pub struct Settings {
+    /// 0.0 to 1.0
+    pub volume: f32,
+    pub text_speed: TextSpeed,
+    pub fullscreen: bool,
+    /// ants stop jittering in place
+    pub reduce_motion: bool,
+    /// hud text gets a solid backdrop so it stays readable over busy scenes
+    pub high_contrast: bool,
+    /// stages whose tutorial was finished or skipped, which is not shown again
+    pub tutorials_done: Vec<Stage>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            volume: 0.75,
+            text_speed: TextSpeed::Normal,
+            fullscreen: false,
+            reduce_motion: false,
+            high_contrast: false,
+            tutorials_done: vec![],
+        }
+    }
+}
+
+impl Settings {
+    /// Falls back to the defaults for anything missing or unreadable.
+    pub fn load() -> Self {
+        let mut settings = Settings::default();
+        let Some(contents) = read_settings_file() else {
+            return settings;
+        };
+        for line in contents.lines() {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let value = value.trim();
+            match key.trim() {
+                "volume" => {
+                    if let Ok(volume) = value.parse::<f32>() {
+                        settings.volume = volume.clamp(0.0, 1.0);
+                    }
+                }
+                "text_speed" => {
+                    if let Some(speed) = TextSpeed::parse(value) {
+                        settings.text_speed = speed;
+                    }
+                }
+                "fullscreen" => settings.fullscreen = value == "true",
+                "reduce_motion" => settings.reduce_motion = value == "true",
+                "high_contrast" => settings.high_contrast = value == "true",
+                "tutorials_done" => {
+                    settings.tutorials_done = value.split(',').filter_map(Stage::parse).collect();
+                }
+                _ => {}
+            }
+        }
+        settings
+    }
+
+    pub fn save(&self) {
+        let tutorials_done: Vec<String> = self
+            .tutorials_done
+            .iter()
+            .map(|stage| stage.to_string())
+            .collect();
+        write_settings_file(&format!(
+            "volume={}\ntext_speed={}\nfullscreen={}\nreduce_motion={}\nhigh_contrast={}\n\
+            tutorials_done={}\n",
+            self.volume,
+            self.text_speed,
+            self.fullscreen,
+            self.reduce_motion,
+            self.high_contrast,
+            tutorials_done.join(","),
+        ));
+    }
+
+    /// Steps through 0%, 25%, .., 100% and back to 0%.
+    pub fn next_volume(&mut self) {
+        self.volume = match self.volume {
+            v if v >= 1.0 => 0.0,
+            v => f32::min(1.0, (v * 4.0).floor() / 4.0 + 0.25),
+        };
+    }
+}
+
+// the web build has no filesystem, settings there only last for the session
+#[cfg(not(target_arch = "wasm32"))]
+fn read_settings_file() -> Option<String> {
+    std::fs::read_to_string(SETTINGS_PATH).ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_settings_file(contents: &str) {
+    if let Err(err) = std::fs::write(SETTINGS_PATH, contents) {
+        eprintln!("could not save settings to {SETTINGS_PATH}: {err}");
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read_settings_file() -> Option<String> {
+    None
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write_settings_file(_contents: &str) {}
@@ -0,0 +1,79 @@
+//! Ants drawn from the spritesheet `gimp/ant_sheet.png`.
+//!
+//! The sheet has one row per `AntLook`, each holding the walk cycle followed by the death frame,
+//! all facing right. The rows are the original ant sprites centred in their frames: the 6x6 ones
+//! of A1 swaying from side to side as they walk, and the 2x1 ones of B1. The death frame is the
+//! same ant faded out.
+
+use macroquad::{
+    color::WHITE,
+    math::{Rect, Vec2},
+};
+
+use crate::batch::SpriteBatch;
+
+/// Width and height of a frame on the sheet.
+const FRAME: f32 = 8.0;
+const WALK_FRAMES: usize = 4;
+const DEATH_FRAME: usize = WALK_FRAMES;
+/// Ticks each frame of the walk cycle is shown for.
+const TICKS_PER_FRAME: usize = 8;
+
+/// Ticks a cleaned ant lies around for.
+pub const CORPSE_TICKS: usize = 30;
+
+/// Row of the sheet.
+#[derive(Clone, Copy)]
+pub enum AntLook {
+    Normal,
+    /// the pale ones that show up once an object is about to be overrun
+    Critical,
+    /// a couple of pixels, far enough away that there is nothing more to see
+    Distant,
+    DistantCritical,
+}
+
+#[derive(Clone, Copy)]
+pub enum Pose {
+    /// `phase` keeps neighbouring ants from stepping in sync
+    Walking {
+        tick: usize,
+        phase: usize,
+    },
+    Dead,
+}
+
+/// Queues an ant `size` units wide centred on `pos`, turned to face along `heading`. `sheet` is
+/// where the spritesheet is on the texture `batch` was started on.
+pub fn push_ant(
+    batch: &mut SpriteBatch,
+    sheet: Rect,
+    pos: Vec2,
+    heading: Vec2,
+    size: f32,
+    look: AntLook,
+    pose: Pose,
+) {
+    let column = match pose {
+        Pose::Walking { tick, phase } => (tick / TICKS_PER_FRAME + phase) % WALK_FRAMES,
+        Pose::Dead => DEATH_FRAME,
+    };
+    let row = match look {
+        AntLook::Normal => 0,
+        AntLook::Critical => 1,
+        AntLook::Distant => 2,
+        AntLook::DistantCritical => 3,
+    };
+    batch.push(
+        Rect::new(
+            sheet.x + column as f32 * FRAME,
+            sheet.y + row as f32 * FRAME,
+            FRAME,
+            FRAME,
+        ),
+        pos,
+        Vec2::splat(size),
+        heading.y.atan2(heading.x),
+        WHITE,
+    );
+}
@@ -0,0 +1,129 @@
+//! Every stage of the game and what goes with it.
+//!
+//! A new stage is a new entry in `STAGES`, along with the files it points at. Each of its level,
+//! story, scene and music is a `ResourceName` variant with an entry in `assets::MANIFEST`, unless
+//! it shares one with another stage, and a level file in `levels/` and a story file in `story/`.
+//! The game and the balancing simulator pick it up from there.
+
+use std::fmt;
+
+use crate::{
+    assets::ResourceName,
+    tutorial::{self, Step},
+};
+
+pub struct StageInfo {
+    /// short name used on the command line and in replay files
+    pub id: &'static str,
+    pub name: &'static str,
+    /// the level file it starts from
+    pub level: ResourceName,
+    /// the story told before it
+    pub story: ResourceName,
+    pub won: &'static str,
+    pub lost: &'static str,
+    /// losing it is shown as ants swarming over the screen
+    pub swarmed_on_loss: bool,
+    /// where winning leads, the end of the game if there is nothing after it
+    pub next: Option<Stage>,
+    pub scene: ResourceName,
+    pub music: ResourceName,
+    /// walked through the first time it is played, if there is anything to show
+    pub tutorial: &'static [Step],
+}
+
+/// In the order they are played.
+const STAGES: [StageInfo; 2] = [
+    StageInfo {
+        id: "A1",
+        name: "Ants at Home",
+        level: ResourceName::LevelA1,
+        story: ResourceName::StoryIntro,
+        won: "You won. The ants are gone and you made a fortune sitting at home.",
+        lost: "You lost. There were two many ants. You died a disgusting death.",
+        swarmed_on_loss: true,
+        next: Some(Stage(1)),
+        scene: ResourceName::ImageA1,
+        music: ResourceName::MusicA1,
+        tutorial: tutorial::A1,
+    },
+    StageInfo {
+        id: "B1",
+        name: "Ants for Hire",
+        level: ResourceName::LevelB1,
+        story: ResourceName::StoryA1Won,
+        won: "You won. Nobody ever found out where the ants came from.",
+        lost: "You lost. The people got suspicious and you were fired from your job.",
+        swarmed_on_loss: false,
+        next: None,
+        scene: ResourceName::ImageB1,
+        music: ResourceName::MusicB1,
+        tutorial: tutorial::B1,
+    },
+];
+
+/// One of `STAGES`.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct Stage(usize);
+
+impl Stage {
+    /// Where the game starts.
+    pub const FIRST: Stage = Stage(0);
+
+    pub fn all() -> impl Iterator<Item = Stage> {
+        (0..STAGES.len()).map(Stage)
+    }
+
+    pub fn parse(id: &str) -> Option<Self> {
+        Stage::all().find(|stage| stage.info().id == id)
+    }
+
+    pub fn info(self) -> &'static StageInfo {
+        &STAGES[self.0]
+    }
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.info().id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assets::{self, AssetKind};
+
+    #[test]
+    fn every_stage_reads_back_from_its_id() {
+        for stage in Stage::all() {
+            assert_eq!(Stage::parse(&stage.to_string()), Some(stage));
+        }
+    }
+
+    #[test]
+    fn every_stage_has_its_own_files() {
+        for stage in Stage::all() {
+            for other in Stage::all().filter(|other| *other != stage) {
+                assert_ne!(stage.info().id, other.info().id);
+                assert_ne!(stage.info().level, other.info().level);
+                assert_ne!(stage.info().story, other.info().story);
+            }
+        }
+    }
+
+    #[test]
+    fn every_stage_has_its_files_in_the_manifest() {
+        for stage in Stage::all() {
+            let info = stage.info();
+            for (name, kind) in [
+                (info.level, AssetKind::Text),
+                (info.story, AssetKind::Text),
+                (info.scene, AssetKind::Texture),
+                (info.music, AssetKind::Sound),
+            ] {
+                assert_eq!(assets::kind(name), kind, "{stage}: {name:?}");
+            }
+        }
+    }
+}
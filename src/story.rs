@@ -0,0 +1,32 @@
+//! A story file being read, a page at a time.
+
+use std::collections::VecDeque;
+
+use crate::assets::ResourceName;
+
+#[derive(Clone)]
+pub struct StoryIter {
+    /// the story file this was read from
+    pub source: ResourceName,
+    pub pages: VecDeque<(ResourceName, Vec<String>)>,
+    /// characters of the current page typed out so far
+    pub revealed: f32,
+}
+
+impl StoryIter {
+    pub fn peek(&self) -> Option<(ResourceName, Vec<String>)> {
+        match self.pages.len() {
+            0 => None,
+            _ => Some(self.pages.front().unwrap().clone()),
+        }
+    }
+}
+
+impl Iterator for StoryIter {
+    type Item = (ResourceName, Vec<String>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.revealed = 0.0;
+        self.pages.pop_front()
+    }
+}
@@ -0,0 +1,30 @@
+//! What the tests of different modules have in common: a small level and a way to play it.
+
+use crate::{
+    data::parse_level,
+    events::Event,
+    level::{update_level, LevelState},
+};
+
+/// An object at 100 dirtiness, its ants walking down the square from (0, 0) to (10, 10).
+pub const OBJECT: &str = "object 100 10 0\nstart 0 0 10 0\nend 0 10 10 10\n";
+
+/// Reads a level file that is known to be right.
+pub fn level(source: &str) -> LevelState {
+    parse_level(source).unwrap()
+}
+
+/// Runs up to `ticks` ticks with nothing clicked, returning everything that happened until the
+/// stage was over.
+pub fn run(level: &mut LevelState, ticks: usize) -> Vec<Event> {
+    let mut all = vec![];
+    for _ in 0..ticks {
+        let (mut events, mut cues) = (vec![], vec![]);
+        update_level(level, &[], &mut events, &mut cues);
+        all.extend(events);
+        if all.contains(&Event::StageWon) || all.contains(&Event::StageLost) {
+            break;
+        }
+    }
+    all
+}
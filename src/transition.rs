@@ -0,0 +1,159 @@
+//! What is drawn while going from one screen to the next. The old screen is covered up, swapped
+//! for the new one while nothing of either can be seen, and the new one is uncovered again.
+
+use macroquad::{
+    color::{Color, BLACK},
+    math::vec2,
+    shapes::draw_rectangle,
+};
+
+use clicker1::{
+    assets::{ResourceName, Resources},
+    batch::SpriteBatch,
+    level::{GAME_HEIGHT, GAME_WIDTH},
+    sprite::{self, AntLook, Pose},
+    util::scramble,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    /// through black
+    Fade,
+    /// black sweeping across from the left
+    Wipe,
+    /// ants pouring in until the screen is black with them, then a fade
+    Death,
+}
+
+impl Kind {
+    /// Frames spent covering the old screen and uncovering the new one.
+    fn frames(self) -> (u32, u32) {
+        match self {
+            Kind::Fade => (20, 20),
+            Kind::Wipe => (25, 25),
+            Kind::Death => (150, 30),
+        }
+    }
+}
+
+/// Going over to a screen of type `T`.
+pub struct Transition<T> {
+    kind: Kind,
+    frame: u32,
+    /// handed over once the old screen is covered
+    next: Option<T>,
+}
+
+impl<T> Transition<T> {
+    pub fn new(kind: Kind, next: T) -> Self {
+        Transition {
+            kind,
+            frame: 0,
+            next: Some(next),
+        }
+    }
+
+    /// Moves on a frame, giving back the next screen on the one where the old one is covered.
+    pub fn advance(&mut self) -> Option<T> {
+        self.frame += 1;
+        match self.frame >= self.kind.frames().0 {
+            true => self.next.take(),
+            false => None,
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        let (covering, uncovering) = self.kind.frames();
+        self.frame >= covering + uncovering
+    }
+
+    /// How much of the screen is hidden, from 0 to 1, and whether it is still being covered.
+    fn coverage(&self) -> (f32, bool) {
+        let (covering, uncovering) = self.kind.frames();
+        match self.frame < covering {
+            true => (self.frame as f32 / covering as f32, true),
+            false => (
+                1.0 - (self.frame - covering) as f32 / uncovering as f32,
+                false,
+            ),
+        }
+    }
+
+    /// Draws over whatever screen is showing.
+    pub fn draw(&self, resources: &Resources, batch: &mut SpriteBatch) {
+        let (width, height) = (GAME_WIDTH as f32, GAME_HEIGHT as f32);
+        let (coverage, covering) = self.coverage();
+        match (self.kind, covering) {
+            (Kind::Wipe, true) => draw_rectangle(0.0, 0.0, width * coverage, height, BLACK),
+            (Kind::Wipe, false) => {
+                let covered = width * coverage;
+                draw_rectangle(width - covered, 0.0, covered, height, BLACK);
+            }
+            (Kind::Death, true) => {
+                draw_swarm(resources, batch, self.frame, coverage);
+                // the last of the screen goes dark under the ants
+                let dark = ((coverage - 0.7) / 0.3).clamp(0.0, 1.0);
+                draw_rectangle(0.0, 0.0, width, height, Color::new(0.0, 0.0, 0.0, dark));
+            }
+            (Kind::Fade, _) | (Kind::Death, false) => {
+                draw_rectangle(0.0, 0.0, width, height, Color::new(0.0, 0.0, 0.0, coverage));
+            }
+        }
+    }
+}
+
+/// Ants crawling in from everywhere, more of them the further along it is.
+fn draw_swarm(resources: &Resources, batch: &mut SpriteBatch, frame: u32, coverage: f32) {
+    const MAX_ANTS: usize = 900;
+    const SIZE: f32 = 24.0;
+    let Some(sheet) = resources.sprite(&ResourceName::AntSheet) else {
+        return;
+    };
+    batch.begin(&resources.atlas.texture);
+    let ants = (coverage * coverage * MAX_ANTS as f32) as usize;
+    for idx in 0..ants {
+        // every ant keeps its place and heading from one frame to the next
+        let angle = scramble(0, idx) * std::f32::consts::TAU;
+        let heading = vec2(angle.cos(), angle.sin());
+        let start = vec2(
+            scramble(1, idx) * GAME_WIDTH as f32,
+            scramble(2, idx) * GAME_HEIGHT as f32,
+        );
+        let pos = start + heading * frame as f32 * 0.5;
+        sprite::push_ant(
+            batch,
+            sheet,
+            vec2(
+                pos.x.rem_euclid(GAME_WIDTH as f32),
+                pos.y.rem_euclid(GAME_HEIGHT as f32),
+            ),
+            heading,
+            SIZE,
+            AntLook::Normal,
+            Pose::Walking {
+                tick: frame as usize,
+                phase: idx,
+            },
+        );
+    }
+    batch.flush();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_next_screen_is_handed_over_once_when_covered() {
+        for kind in [Kind::Fade, Kind::Wipe, Kind::Death] {
+            let mut transition = Transition::new(kind, "next");
+            let mut handed_over = vec![];
+            while !transition.is_done() {
+                if let Some(next) = transition.advance() {
+                    handed_over.push((next, transition.coverage().0));
+                }
+            }
+            assert_eq!(handed_over, [("next", 1.0)]);
+        }
+    }
+}
@@ -0,0 +1,279 @@
+//! Scripted walkthroughs over the start of a stage. Every step points at something, says what to
+//! do with it and waits for the simulation to show it was done.
+//!
+//! The steps of a stage go in its `StageInfo`. Objects are pointed at by their place in the level
+//! file, so a step has to change along with the file if its objects are reordered.
+
+use crate::level::LevelState;
+
+/// What a step draws attention to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Focus {
+    Nothing,
+    /// the `n`th object, shown with a gauge of its dirtiness
+    Object(usize),
+    RepellantButton,
+    /// the countdown of a stage with a shift
+    ShiftTimer,
+    /// every drone flying over the scene
+    Drones,
+}
+
+impl Focus {
+    /// Whether `level` has what this points at on screen.
+    pub fn is_shown(self, level: &LevelState) -> bool {
+        match self {
+            Focus::Nothing | Focus::RepellantButton => true,
+            Focus::Object(object) => object < level.objects.len(),
+            Focus::ShiftTimer => level.shift_left().is_some(),
+            Focus::Drones => level.rules.drones(level.repellants) > 0,
+        }
+    }
+}
+
+/// What it takes to get past a step.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Until {
+    /// a tap anywhere, for steps that only explain
+    Tapped,
+    /// the `n`th object got this much cleaner than when the step began
+    Cleaned(usize, u8),
+    /// the `n`th object got this much dirtier than when the step began
+    Deposited(usize, u8),
+    /// a repellant was bought
+    Bought,
+}
+
+pub struct Step {
+    pub lines: &'static [&'static str],
+    pub focus: Focus,
+    pub until: Until,
+    /// the stage waits underneath instead of being played, its clock included
+    pub stands_still: bool,
+}
+
+pub const A1: &[Step] = &[
+    Step {
+        lines: &[
+            "Ants are crawling all over your coffee cup.",
+            "The meter next to it shows how dirty it is.",
+        ],
+        focus: Focus::Object(0),
+        until: Until::Tapped,
+        stands_still: false,
+    },
+    Step {
+        lines: &[
+            "Hold down on the cup to squish them and",
+            "watch the meter fall.",
+        ],
+        focus: Focus::Object(0),
+        until: Until::Cleaned(0, 20),
+        stands_still: false,
+    },
+    Step {
+        lines: &[
+            "You only get paid while you are not cleaning, and",
+            "once everything is full of ants it is over.",
+        ],
+        focus: Focus::Nothing,
+        until: Until::Tapped,
+        stands_still: false,
+    },
+    Step {
+        lines: &[
+            "Repellant slows down how fast they multiply.",
+            "Buy some as soon as you can afford it.",
+        ],
+        focus: Focus::RepellantButton,
+        until: Until::Bought,
+        stands_still: false,
+    },
+];
+
+/// The clock only starts once it is all explained.
+pub const B1: &[Step] = &[
+    Step {
+        lines: &[
+            "This house already has some of your ants in it.",
+            "Keep its gauge between the red lines: with too few they",
+            "clean up, with too many the people get suspicious.",
+        ],
+        focus: Focus::Object(0),
+        until: Until::Tapped,
+        stands_still: true,
+    },
+    Step {
+        lines: &[
+            "Your shift is four minutes long.",
+            "Make your money before it is over.",
+        ],
+        focus: Focus::ShiftTimer,
+        until: Until::Tapped,
+        stands_still: true,
+    },
+    Step {
+        lines: &[
+            "Every drone makes the ants you deposit pay more.",
+            "Buy more of them with what you earn.",
+        ],
+        focus: Focus::Drones,
+        until: Until::Tapped,
+        stands_still: true,
+    },
+    Step {
+        lines: &[
+            "The clock is running. Hold down on the house",
+            "to let more ants in and get paid.",
+        ],
+        focus: Focus::Object(0),
+        until: Until::Deposited(0, 10),
+        stands_still: false,
+    },
+];
+
+/// How far a stage's tutorial got.
+pub struct Tutorial {
+    steps: &'static [Step],
+    step: usize,
+    /// the dirtiness of every object and the repellants as the current step began, taken on the
+    /// first update of the step
+    from: Option<(Vec<u8>, u16)>,
+}
+
+impl Tutorial {
+    pub fn new(steps: &'static [Step]) -> Self {
+        Tutorial {
+            steps,
+            step: 0,
+            from: None,
+        }
+    }
+
+    /// The step being shown, `None` once all of them are done.
+    pub fn step(&self) -> Option<&'static Step> {
+        self.steps.get(self.step)
+    }
+
+    /// Moves on to the next step if `level` shows the current one was done, or passes it over if
+    /// it waits on an object `level` does not have.
+    pub fn update(&mut self, level: &LevelState, tapped: bool) {
+        let Some(step) = self.step() else {
+            return;
+        };
+        let (dirtiness, repellants) = self.from.get_or_insert_with(|| {
+            let dirtiness = level
+                .objects
+                .iter()
+                .map(|object| object.dirtiness)
+                .collect();
+            (dirtiness, level.repellants)
+        });
+        let done = match step.until {
+            Until::Tapped => tapped,
+            Until::Cleaned(object, by) => match watch(level, dirtiness, object) {
+                Some((now, from)) => now.saturating_add(by) <= from,
+                None => true,
+            },
+            Until::Deposited(object, by) => match watch(level, dirtiness, object) {
+                Some((now, from)) => now >= from.saturating_add(by),
+                None => true,
+            },
+            Until::Bought => level.repellants > *repellants,
+        };
+        if done {
+            self.step += 1;
+            self.from = None;
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.step().is_none()
+    }
+}
+
+/// The dirtiness of `object` now and as the step began. `None` if the level does not have it, or
+/// did not when the step began, and the step is passed over as there is nothing to wait for.
+fn watch(level: &LevelState, from: &[u8], object: usize) -> Option<(u8, u8)> {
+    Some((level.objects.get(object)?.dirtiness, *from.get(object)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        assets,
+        stages::Stage,
+        testing::{level, OBJECT},
+    };
+
+    const LEVEL: &str = "difficulty easy\nmoney_goal 1000\nrepellant_price 1\n";
+
+    #[test]
+    fn steps_wait_for_the_level_to_show_they_were_done() {
+        let mut level = level(&format!("{LEVEL}{OBJECT}"));
+        let mut tutorial = Tutorial::new(A1);
+
+        tutorial.update(&level, false);
+        assert_eq!(tutorial.step, 0);
+        tutorial.update(&level, true);
+        assert_eq!(tutorial.step, 1);
+
+        // dirt coming in by itself does not count as cleaning
+        tutorial.update(&level, true);
+        level.objects[0].dirtiness = 110;
+        tutorial.update(&level, true);
+        assert_eq!(tutorial.step, 1);
+        level.objects[0].dirtiness = 80;
+        tutorial.update(&level, false);
+        assert_eq!(tutorial.step, 2);
+
+        tutorial.update(&level, true);
+        tutorial.update(&level, true);
+        assert_eq!(tutorial.step, 3);
+        level.repellants += 1;
+        tutorial.update(&level, false);
+        assert!(tutorial.is_done());
+    }
+
+    #[test]
+    fn depositing_counts_dirt_going_up() {
+        let mut level = level(&format!("{}{OBJECT}", LEVEL.replace("easy", "medium")));
+        let mut tutorial = Tutorial::new(&B1[3..]);
+        tutorial.update(&level, true);
+        level.objects[0].dirtiness = 109;
+        tutorial.update(&level, true);
+        assert!(!tutorial.is_done());
+        level.objects[0].dirtiness = 110;
+        tutorial.update(&level, false);
+        assert!(tutorial.is_done());
+    }
+
+    #[test]
+    fn every_step_points_at_something_in_its_stage() {
+        for stage in Stage::all() {
+            let path = assets::path(stage.info().level);
+            let level = level(&std::fs::read_to_string(path).unwrap());
+            for step in stage.info().tutorial {
+                assert!(step.focus.is_shown(&level), "{stage}: {:?}", step.focus);
+            }
+        }
+    }
+
+    #[test]
+    fn steps_about_missing_objects_are_passed_over() {
+        let mut tutorial = Tutorial::new(B1);
+        let empty = level("difficulty medium\nmoney_goal 1000\n");
+        for _ in 0..3 {
+            tutorial.update(&empty, true);
+        }
+        tutorial.update(&empty, false);
+        assert!(tutorial.is_done());
+
+        // reloaded without the object while its step is going on
+        let mut tutorial = Tutorial::new(&B1[3..]);
+        tutorial.update(&level(&format!("difficulty medium\n{OBJECT}")), false);
+        tutorial.update(&empty, false);
+        assert!(tutorial.is_done());
+    }
+}
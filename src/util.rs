@@ -1,55 +1,89 @@
-use macroquad::math::{vec2, Vec2};
+use macroquad::math::Vec2;
 
 pub fn pythogoras(x: f32, y: f32) -> f32 {
     (x * x + y * y).sqrt()
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub struct LineSegment {
     pub start: Vec2,
     pub end: Vec2,
 }
 
+/// Which ends of a segment get a point when sampling it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Endpoints {
+    /// the start but not the end, so segments laid end to end never share a point
+    Start,
+    End,
+    Both,
+    /// points sit in the middle of equal slices of the segment
+    Neither,
+}
+
+/// Exactly `amount` evenly spaced points along a `LineSegment`, see `LineSegment::sample`.
+#[derive(Clone)]
 pub struct LineSegmentPointsOn {
-    interval: f32,
-    current: Option<Vec2>,
+    start: Vec2,
     end: Vec2,
+    /// position of the first point and the distance between points, as a fraction of the segment
+    first: f32,
+    spacing: f32,
+    jitter: f32,
+    seed: u32,
+    next: usize,
+    amount: usize,
+}
+
+impl LineSegmentPointsOn {
+    /// Moves every point along the segment by up to half of `amount` times the spacing, the same
+    /// way every time for the same `seed`. Points never leave the segment.
+    pub fn jitter(self, amount: f32, seed: u32) -> Self {
+        LineSegmentPointsOn {
+            jitter: amount.clamp(0.0, 1.0),
+            seed,
+            ..self
+        }
+    }
 }
 
 impl Iterator for LineSegmentPointsOn {
     type Item = Vec2;
 
     fn next(&mut self) -> Option<Vec2> {
-        let current = self.current?;
-        let mut next = Some(vec2(
-            current.x
-                + self.interval
-                    * ((self.end.x - current.x)
-                        / pythogoras(self.end.x - current.x, self.end.y - current.y)),
-            current.y
-                + self.interval
-                    * ((self.end.y - current.y)
-                        / pythogoras(self.end.x - current.x, self.end.y - current.y)),
-        ));
-        match pythogoras(self.end.x - current.x, self.end.y - current.y)
-            - pythogoras(next.unwrap().x - current.x, next.unwrap().y - current.y)
-        {
-            n if n > 0.0 => {
-                // self.current = Some(next.clone());
-                std::mem::swap(&mut self.current, &mut next);
-                next
-            }
-            _ => {
-                self.current = None;
-                None
-            }
+        if self.next == self.amount {
+            return None;
         }
+        let idx = self.next;
+        self.next += 1;
+
+        let shift = (scramble(self.seed, idx) - 0.5) * self.jitter * self.spacing;
+        let t = (self.first + idx as f32 * self.spacing + shift).clamp(0.0, 1.0);
+        Some(self.start.lerp(self.end, t))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let left = self.amount - self.next;
+        (left, Some(left))
     }
 }
 
+impl ExactSizeIterator for LineSegmentPointsOn {}
+
+/// A number from 0 to 1 that looks random but is always the same for the same inputs.
+pub fn scramble(seed: u32, idx: usize) -> f32 {
+    let mut x = seed ^ (idx as u32).wrapping_mul(0x9e37_79b9);
+    x ^= x >> 16;
+    x = x.wrapping_mul(0x7feb_352d);
+    x ^= x >> 15;
+    x = x.wrapping_mul(0x846c_a68b);
+    x ^= x >> 16;
+    (x >> 8) as f32 / (1 << 24) as f32
+}
+
 //https://stackoverflow.com/a/2049593
 fn sign(p1: Vec2, p2: Vec2, p3: Vec2) -> f32 {
-    return (p1.x - p3.x) * (p2.y - p3.y) - (p2.x - p3.x) * (p1.y - p3.y);
+    (p1.x - p3.x) * (p2.y - p3.y) - (p2.x - p3.x) * (p1.y - p3.y)
 }
 fn in_triange(p: Vec2, v1: Vec2, v2: Vec2, v3: Vec2) -> bool {
     let d1 = sign(p, v1, v2);
@@ -59,7 +93,7 @@ fn in_triange(p: Vec2, v1: Vec2, v2: Vec2, v3: Vec2) -> bool {
     let has_neg = (d1 < 0.0) || (d2 < 0.0) || (d3 < 0.0);
     let has_pos = (d1 > 0.0) || (d2 > 0.0) || (d3 > 0.0);
 
-    return !(has_neg && has_pos);
+    !(has_neg && has_pos)
 }
 
 impl LineSegment {
@@ -74,13 +108,31 @@ impl LineSegment {
         }
     }
 
+    /// `amount` points from the start, evenly spaced up to but not including the end.
     pub fn points_on(&self, amount: usize) -> LineSegmentPointsOn {
-        let interval =
-            pythogoras(self.start.x - self.end.x, self.start.y - self.end.y) / amount as f32;
+        self.sample(amount, Endpoints::Start)
+    }
+
+    /// `amount` evenly spaced points, which of the ends are included is up to `endpoints`.
+    ///
+    /// A segment without length gives `amount` copies of its start.
+    pub fn sample(&self, amount: usize, endpoints: Endpoints) -> LineSegmentPointsOn {
+        let slices = amount.max(1) as f32;
+        let (first, spacing) = match endpoints {
+            Endpoints::Start => (0.0, 1.0 / slices),
+            Endpoints::End => (1.0 / slices, 1.0 / slices),
+            Endpoints::Both => (0.0, 1.0 / (amount.max(2) - 1) as f32),
+            Endpoints::Neither => (0.5 / slices, 1.0 / slices),
+        };
         LineSegmentPointsOn {
-            interval,
-            current: Some(self.start.clone()),
-            end: self.end.clone(),
+            start: self.start,
+            end: self.end,
+            first,
+            spacing,
+            jitter: 0.0,
+            seed: 0,
+            next: 0,
+            amount,
         }
     }
 
@@ -90,3 +142,320 @@ impl LineSegment {
             || in_triange(point, other.start, other.end, self.end)
     }
 }
+
+// a cubic curve is flattened into this many straight pieces
+const BEZIER_STEPS: usize = 24;
+
+/// A trail with some width, walked by distance travelled rather than by how it was drawn.
+///
+/// Curves are flattened into a polyline up front, so the distance along the path is measured the
+/// same way for both.
+#[derive(Clone, Debug)]
+pub struct Path {
+    points: Vec<Vec2>,
+    /// distance along the path at each of `points`
+    lengths: Vec<f32>,
+    pub width: f32,
+}
+
+impl Path {
+    /// Straight from one point to the next, needs at least one point.
+    pub fn polyline(points: Vec<Vec2>, width: f32) -> Self {
+        assert!(!points.is_empty(), "a path needs at least one point");
+        let mut length = 0.0;
+        let mut lengths = Vec::with_capacity(points.len());
+        lengths.push(0.0);
+        for pair in points.windows(2) {
+            length += pair[0].distance(pair[1]);
+            lengths.push(length);
+        }
+        Path {
+            points,
+            lengths,
+            width,
+        }
+    }
+
+    /// A cubic Bézier curve from `p0` to `p3`, bent towards `p1` and `p2`.
+    pub fn bezier(p0: Vec2, p1: Vec2, p2: Vec2, p3: Vec2, width: f32) -> Self {
+        let points = (0..=BEZIER_STEPS)
+            .map(|step| {
+                let t = step as f32 / BEZIER_STEPS as f32;
+                let u = 1.0 - t;
+                p0 * u * u * u + p1 * 3.0 * u * u * t + p2 * 3.0 * u * t * t + p3 * t * t * t
+            })
+            .collect();
+        Path::polyline(points, width)
+    }
+
+    pub fn length(&self) -> f32 {
+        *self.lengths.last().unwrap()
+    }
+
+    pub fn start(&self) -> Vec2 {
+        self.points[0]
+    }
+
+    pub fn end(&self) -> Vec2 {
+        *self.points.last().unwrap()
+    }
+
+    /// The point `distance` along the path and the direction the path goes there.
+    ///
+    /// `distance` is clamped to the path, a path without length points right.
+    pub fn at(&self, distance: f32) -> (Vec2, Vec2) {
+        let distance = distance.clamp(0.0, self.length());
+        // the piece `distance` falls on, past any that have no length
+        let idx = self
+            .lengths
+            .partition_point(|length| *length <= distance)
+            .clamp(1, self.points.len().max(2) - 1);
+        let Some((from, to)) = self.points.get(idx - 1).zip(self.points.get(idx)) else {
+            return (self.points[0], Vec2::X);
+        };
+        let piece = self.lengths[idx] - self.lengths[idx - 1];
+        if piece <= 0.0 {
+            return (*from, Vec2::X);
+        }
+        let t = (distance - self.lengths[idx - 1]) / piece;
+        // lerp can be off by a rounding error at the end of the piece, which should be exact
+        let pos = match t < 1.0 {
+            true => from.lerp(*to, t),
+            false => *to,
+        };
+        (pos, (*to - *from) / piece)
+    }
+
+    /// Whether `point` is within the width of the path.
+    pub fn contains(&self, point: Vec2) -> bool {
+        let reach = self.width / 2.0;
+        match self.points.len() {
+            1 => self.points[0].distance(point) <= reach,
+            _ => self
+                .points
+                .windows(2)
+                .any(|pair| distance_to_segment(point, pair[0], pair[1]) <= reach),
+        }
+    }
+}
+
+fn distance_to_segment(point: Vec2, start: Vec2, end: Vec2) -> f32 {
+    let along = end - start;
+    let t = match along.length_squared() {
+        0.0 => 0.0,
+        length_squared => ((point - start).dot(along) / length_squared).clamp(0.0, 1.0),
+    };
+    point.distance(start + along * t)
+}
+
+#[cfg(test)]
+mod tests {
+    use macroquad::math::vec2;
+    use proptest::prelude::*;
+
+    use super::*;
+
+    fn coord() -> impl Strategy<Value = f32> {
+        -2000.0f32..2000.0
+    }
+
+    fn segment() -> impl Strategy<Value = LineSegment> {
+        prop_oneof![
+            (coord(), coord(), coord(), coord())
+                .prop_map(|(x1, y1, x2, y2)| LineSegment::new((x1, y1), (x2, y2))),
+            // zero length
+            (coord(), coord()).prop_map(|(x, y)| LineSegment::new((x, y), (x, y))),
+        ]
+    }
+
+    fn endpoints() -> impl Strategy<Value = Endpoints> {
+        prop_oneof![
+            Just(Endpoints::Start),
+            Just(Endpoints::End),
+            Just(Endpoints::Both),
+            Just(Endpoints::Neither),
+        ]
+    }
+
+    fn on_segment(segment: &LineSegment, point: Vec2) -> bool {
+        let tolerance = 1e-3 * (1.0 + segment.start.distance(segment.end));
+        point.is_finite()
+            && (segment.start.distance(point) + point.distance(segment.end)
+                - segment.start.distance(segment.end))
+            .abs()
+                <= tolerance
+    }
+
+    /// Up to 8 points, some of them repeating the one before so the path has pieces without length.
+    fn path() -> impl Strategy<Value = Path> {
+        (
+            prop::collection::vec((coord(), coord(), prop::bool::weighted(0.2)), 1..8),
+            0.0f32..100.0,
+        )
+            .prop_map(|(coords, width)| {
+                let mut points: Vec<Vec2> = vec![];
+                for (x, y, repeat) in coords {
+                    match (repeat, points.last()) {
+                        (true, Some(&last)) => points.push(last),
+                        _ => points.push(vec2(x, y)),
+                    }
+                }
+                Path::polyline(points, width)
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn walks_the_path_by_distance(path in path(), from in 0.0f32..1.0, to in 0.0f32..1.0) {
+            let tolerance = 1e-3 * (1.0 + path.length());
+            // every point is as far along as it says, going straight from one to the next
+            for (point, length) in path.points.iter().zip(&path.lengths) {
+                prop_assert!(path.at(*length).0.distance(*point) <= tolerance);
+            }
+            // and nowhere in between does it go any faster
+            let (from, to) = (from * path.length(), to * path.length());
+            let moved = path.at(from).0.distance(path.at(to).0);
+            prop_assert!(moved <= (to - from).abs() + tolerance, "{moved} > {}", to - from);
+        }
+
+        #[test]
+        fn ends_on_the_last_point(path in path()) {
+            prop_assert_eq!(path.at(path.length()).0, path.end());
+            prop_assert_eq!(path.at(f32::MAX).0, path.end());
+            prop_assert_eq!(path.at(-1.0).0, path.start());
+        }
+
+        #[test]
+        fn heads_one_step_at_a_time(path in path(), along in 0.0f32..1.0) {
+            let (_, heading) = path.at(along * path.length());
+            prop_assert!((heading.length() - 1.0).abs() <= 1e-3, "{heading}");
+        }
+
+        #[test]
+        fn contains_what_is_within_its_width(
+            path in path(),
+            along in 0.0f32..1.0,
+            side in -0.99f32..0.99,
+        ) {
+            let (pos, heading) = path.at(along * path.length());
+            let point = pos + heading.perp() * side * path.width / 2.0;
+            prop_assert!(path.contains(point));
+        }
+
+        #[test]
+        fn yields_exactly_amount(
+            segment in segment(),
+            amount in 0usize..500,
+            endpoints in endpoints(),
+            jitter in 0.0f32..1.0,
+            seed: u32,
+        ) {
+            let points = segment.sample(amount, endpoints).jitter(jitter, seed);
+            prop_assert_eq!(points.len(), amount);
+            prop_assert_eq!(points.count(), amount);
+        }
+
+        #[test]
+        fn stays_on_the_segment(
+            segment in segment(),
+            amount in 0usize..200,
+            endpoints in endpoints(),
+            jitter in 0.0f32..1.0,
+            seed: u32,
+        ) {
+            for point in segment.sample(amount, endpoints).jitter(jitter, seed) {
+                prop_assert!(on_segment(&segment, point), "{point} is off the segment");
+            }
+        }
+
+        #[test]
+        fn evenly_spaced(segment in segment(), amount in 2usize..200, endpoints in endpoints()) {
+            let points: Vec<_> = segment.sample(amount, endpoints).collect();
+            let spacing = points[0].distance(points[1]);
+            let tolerance = 1e-3 * (1.0 + segment.start.distance(segment.end));
+            for pair in points.windows(2) {
+                prop_assert!((pair[0].distance(pair[1]) - spacing).abs() <= tolerance);
+            }
+        }
+
+        #[test]
+        fn includes_the_ends_asked_for(
+            segment in segment(),
+            amount in 2usize..200,
+            endpoints in endpoints(),
+        ) {
+            let points: Vec<_> = segment.sample(amount, endpoints).collect();
+            let length = segment.start.distance(segment.end);
+            let tolerance = 1e-3 * (1.0 + length);
+            // how far the first and last points are from the start and end
+            let gaps = (
+                points[0].distance(segment.start),
+                points[amount - 1].distance(segment.end),
+            );
+            let (none, half) = (0.0, length / (2 * amount) as f32);
+            let expected = match endpoints {
+                Endpoints::Start => (none, length / amount as f32),
+                Endpoints::End => (length / amount as f32, none),
+                Endpoints::Both => (none, none),
+                Endpoints::Neither => (half, half),
+            };
+            prop_assert!((gaps.0 - expected.0).abs() <= tolerance, "{gaps:?} != {expected:?}");
+            prop_assert!((gaps.1 - expected.1).abs() <= tolerance, "{gaps:?} != {expected:?}");
+        }
+
+        #[test]
+        fn jitter_is_repeatable(segment in segment(), amount in 0usize..100, seed: u32) {
+            let first: Vec<_> = segment.points_on(amount).jitter(0.5, seed).collect();
+            let second: Vec<_> = segment.points_on(amount).jitter(0.5, seed).collect();
+            prop_assert_eq!(first, second);
+        }
+    }
+
+    #[test]
+    fn zero_length_segment_repeats_its_start() {
+        let segment = LineSegment::new((614.0, 220.0), (614.0, 220.0));
+        let points: Vec<_> = segment.points_on(800).collect();
+        assert_eq!(points.len(), 800);
+        assert!(points.iter().all(|point| *point == vec2(614.0, 220.0)));
+    }
+
+    #[test]
+    fn a_path_of_one_point_stays_there() {
+        let path = Path::polyline(vec![vec2(10.0, 20.0)], 4.0);
+        assert_eq!(path.length(), 0.0);
+        assert_eq!(path.at(0.0), (vec2(10.0, 20.0), Vec2::X));
+        assert_eq!(path.at(5.0), (vec2(10.0, 20.0), Vec2::X));
+        assert_eq!(path.end(), path.start());
+        assert!(path.contains(vec2(11.9, 20.0)));
+        assert!(!path.contains(vec2(12.1, 20.0)));
+    }
+
+    #[test]
+    fn contains_stops_at_the_width() {
+        let path = Path::polyline(vec![vec2(0.0, 0.0), vec2(100.0, 0.0)], 10.0);
+        assert!(path.contains(vec2(50.0, 4.9)));
+        assert!(path.contains(vec2(50.0, -4.9)));
+        assert!(!path.contains(vec2(50.0, 5.1)));
+        assert!(path.contains(vec2(104.9, 0.0)));
+        assert!(!path.contains(vec2(105.1, 0.0)));
+    }
+
+    #[test]
+    fn uneven_pieces_are_walked_at_the_same_speed() {
+        // 10 long, then nothing, then 90
+        let path = Path::polyline(
+            vec![
+                vec2(0.0, 0.0),
+                vec2(10.0, 0.0),
+                vec2(10.0, 0.0),
+                vec2(10.0, 90.0),
+            ],
+            1.0,
+        );
+        assert_eq!(path.length(), 100.0);
+        assert_eq!(path.at(5.0), (vec2(5.0, 0.0), Vec2::X));
+        assert_eq!(path.at(10.0).0, vec2(10.0, 0.0));
+        assert_eq!(path.at(55.0), (vec2(10.0, 45.0), Vec2::Y));
+        assert_eq!(path.at(100.0).0, path.end());
+    }
+}